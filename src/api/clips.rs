@@ -33,6 +33,9 @@ pub struct Creator {
     pub id: String,
     pub name: String,
     pub username: String,
+    /// profile picture, missing for creators who never set one
+    #[serde(default)]
+    pub avatar_url: Option<Url>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,213 @@
+use crate::config::{Animation, Direction, Easing, Effect, StyleConfig, Transition};
+
+/// overshoot of the `back` easing
+const BACK_C1: f64 = 1.70158;
+/// period of the `elastic` easing
+const ELASTIC_C4: f64 = std::f64::consts::TAU / 3.0;
+
+/// how far along (0 to 1) a transition of `duration_sec` starting at `start_sec` is, with `time`
+/// being the name the filter gives the current time
+fn progress_expr(time: &str, start_sec: f64, duration_sec: f64) -> String {
+    if duration_sec <= 0.0 {
+        return format!("gte({time},{start_sec})");
+    }
+
+    format!("min(max({time}-{start_sec},0)/{duration_sec},1)")
+}
+
+/// eases `p` so it starts fast and settles into place, used when entering
+fn ease_out(easing: Easing, p: &str) -> String {
+    match easing {
+        Easing::Linear => p.to_string(),
+        Easing::Cubic => format!("(1-pow(1-{p},3))"),
+        Easing::Quint => format!("(1-pow(1-{p},5))"),
+        Easing::Back => {
+            let c3 = BACK_C1 + 1.0;
+            format!("(1+{c3}*pow({p}-1,3)+{BACK_C1}*pow({p}-1,2))")
+        }
+        Easing::Elastic => {
+            format!("if(gte({p},1),1,pow(2,-10*{p})*sin(({p}*10-0.75)*{ELASTIC_C4})+1)")
+        }
+    }
+}
+
+/// eases `p` so it starts slowly and speeds up, used when leaving (the mirror of [`ease_out`])
+fn ease_in(easing: Easing, p: &str) -> String {
+    match easing {
+        Easing::Linear => p.to_string(),
+        Easing::Cubic => format!("pow({p},3)"),
+        Easing::Quint => format!("pow({p},5)"),
+        _ => format!("(1-{})", ease_out(easing, &format!("(1-{p})"))),
+    }
+}
+
+/// the side of the frame text moves to, with `anchor` picking the side the text sits against
+const fn resolve_direction(direction: Direction, style: &StyleConfig) -> Direction {
+    match direction {
+        Direction::Anchor if style.anchor.is_left() => Direction::Left,
+        Direction::Anchor => Direction::Right,
+        other => other,
+    }
+}
+
+const fn slides(effect: Effect) -> bool {
+    matches!(effect, Effect::SlideFade | Effect::Slide)
+}
+
+const fn fades(effect: Effect) -> bool {
+    matches!(effect, Effect::SlideFade | Effect::Fade)
+}
+
+impl Transition {
+    /// seconds until the transition is completely done
+    pub const fn length_sec(&self) -> f64 {
+        match self.effect {
+            Effect::SlideFade => self.duration.max(self.fade),
+            Effect::Slide => self.duration,
+            Effect::Fade => self.fade,
+            Effect::Cut => 0.0,
+        }
+    }
+}
+
+/// `x` and `y` expressions of a line (or anything else moving along with it)
+pub struct LinePosition {
+    pub x: String,
+    pub y: String,
+}
+
+/// what a filter calls the size of the frame, and of the thing it places in it
+pub struct SizeNames {
+    pub frame_w: &'static str,
+    pub frame_h: &'static str,
+    pub w: &'static str,
+    pub h: &'static str,
+}
+
+/// drawtext's names, where `w`/`h` is the frame and `tw`/`th` the text
+pub const DRAWTEXT_SIZES: SizeNames = SizeNames {
+    frame_w: "w",
+    frame_h: "h",
+    w: "tw",
+    h: "th",
+};
+
+/// overlay's names, where `W`/`H` is the frame and `w`/`h` the picture laid over it
+pub const OVERLAY_SIZES: SizeNames = SizeNames {
+    frame_w: "W",
+    frame_h: "H",
+    w: "w",
+    h: "h",
+};
+
+/// adds the offset for being `amount` (0 to 1) of the way off screen towards `direction`
+fn push_offset(
+    position: &mut LinePosition,
+    sizes: &SizeNames,
+    base_x: &str,
+    base_y: &str,
+    direction: Direction,
+    amount: &str,
+) {
+    let LinePosition { x, y } = position;
+    let SizeNames {
+        frame_w,
+        frame_h,
+        w,
+        h,
+    } = sizes;
+
+    match direction {
+        Direction::Left => *x = format!("{x}-({base_x}+{w})*{amount}"),
+        // anchor has already been resolved to left or right by now
+        Direction::Right | Direction::Anchor => {
+            *x = format!("{x}+({frame_w}-({base_x}))*{amount}");
+        }
+        Direction::Top => *y = format!("{y}-({base_y}+{h})*{amount}"),
+        Direction::Bottom => *y = format!("{y}+({frame_h}-({base_y}))*{amount}"),
+    }
+}
+
+/// where a line is at time `t`, entering at `t_in_sec` and leaving at `t_out_sec`
+pub fn position(
+    animation: &Animation,
+    style: &StyleConfig,
+    base_x: &str,
+    base_y: &str,
+    t_in_sec: f64,
+    t_out_sec: f64,
+) -> LinePosition {
+    position_in(
+        &DRAWTEXT_SIZES,
+        animation,
+        style,
+        base_x,
+        base_y,
+        t_in_sec,
+        t_out_sec,
+    )
+}
+
+/// [`position`] for filters other than drawtext, which name the sizes differently
+pub fn position_in(
+    sizes: &SizeNames,
+    animation: &Animation,
+    style: &StyleConfig,
+    base_x: &str,
+    base_y: &str,
+    t_in_sec: f64,
+    t_out_sec: f64,
+) -> LinePosition {
+    let mut position = LinePosition {
+        x: base_x.to_string(),
+        y: base_y.to_string(),
+    };
+
+    let Animation { enter, exit, .. } = animation;
+
+    if slides(enter.effect) {
+        let eased = ease_out(enter.easing, &progress_expr("t", t_in_sec, enter.duration));
+        let direction = resolve_direction(enter.direction, style);
+        push_offset(
+            &mut position,
+            sizes,
+            base_x,
+            base_y,
+            direction,
+            &format!("(1-{eased})"),
+        );
+    }
+
+    if slides(exit.effect) {
+        let eased = ease_in(exit.easing, &progress_expr("t", t_out_sec, exit.duration));
+        let direction = resolve_direction(exit.direction, style);
+        push_offset(&mut position, sizes, base_x, base_y, direction, &eased);
+    }
+
+    position
+}
+
+/// how visible a line is at time `t`, entering at `t_in_sec` and leaving at `t_out_sec`
+pub fn alpha(animation: &Animation, t_in_sec: f64, t_out_sec: f64) -> String {
+    alpha_at("t", animation, t_in_sec, t_out_sec)
+}
+
+/// [`alpha`] for filters that call the current time something other than `t`
+pub fn alpha_at(time: &str, animation: &Animation, t_in_sec: f64, t_out_sec: f64) -> String {
+    let Animation { enter, exit, .. } = animation;
+
+    let shown = if fades(enter.effect) {
+        progress_expr(time, t_in_sec, enter.fade)
+    } else {
+        format!("gte({time},{t_in_sec})")
+    };
+
+    let hidden = if fades(exit.effect) {
+        progress_expr(time, t_out_sec, exit.fade)
+    } else {
+        // a line sliding out without fading stays visible until it's off screen
+        format!("gte({time},{})", t_out_sec + exit.length_sec())
+    };
+
+    format!("{shown}-{hidden}")
+}
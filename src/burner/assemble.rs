@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::consts::OUTPUT_SAMPLE_RATE;
+use super::encoding::{audio_args, video_args};
+use super::utils::{
+    encode_progress_style, get_video_duration_sec, has_audio_stream, run_ffmpeg_with_progress,
+};
+use crate::config::EncodingProfile;
+
+/// joins `inputs` (in order) into a single video
+///
+/// every input is scaled, resampled and given a stereo audio track (silent if it had none)
+/// before going into the concat filter, so clips recorded with different settings can't make
+/// the join fail
+pub fn assemble(
+    inputs: &[PathBuf],
+    output: &Path,
+    profile: &EncodingProfile,
+    crf: Option<i32>,
+) -> Result<()> {
+    if inputs.is_empty() {
+        anyhow::bail!("there is nothing to assemble");
+    }
+
+    let (width, height, fps) = (profile.width, profile.height, profile.fps);
+    let pix_fmt = &profile.pix_fmt;
+
+    let mut args = vec!["-y".to_string()];
+    let mut filter = String::new();
+    let mut concat_inputs = String::new();
+    let mut total_sec = 0.0;
+
+    for (index, input) in inputs.iter().enumerate() {
+        let duration_sec = get_video_duration_sec(input)
+            .with_context(|| format!("failed to get the duration of {}", input.display()))?;
+        total_sec += duration_sec;
+
+        args.push("-i".to_string());
+        args.push(input.to_string_lossy().into_owned());
+
+        let _ = write!(
+            filter,
+            "[{index}:v]scale={width}:{height}:force_original_aspect_ratio=decrease,\
+pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps},format={pix_fmt}[v{index}];"
+        );
+
+        if has_audio_stream(input)? {
+            let _ = write!(
+                filter,
+                "[{index}:a]aresample={OUTPUT_SAMPLE_RATE},aformat=sample_fmts=fltp:channel_layouts=stereo[a{index}];"
+            );
+        } else {
+            let _ = write!(
+                filter,
+                "anullsrc=channel_layout=stereo:sample_rate={OUTPUT_SAMPLE_RATE},atrim=duration={duration_sec},aformat=sample_fmts=fltp[a{index}];"
+            );
+        }
+
+        let _ = write!(concat_inputs, "[v{index}][a{index}]");
+    }
+
+    let _ = write!(
+        filter,
+        "{concat_inputs}concat=n={}:v=1:a=1[outv][outa]",
+        inputs.len()
+    );
+
+    args.extend(
+        [
+            "-filter_complex",
+            &filter,
+            "-map",
+            "[outv]",
+            "-map",
+            "[outa]",
+            "-movflags",
+            "+faststart",
+        ]
+        .map(str::to_string),
+    );
+    args.extend(video_args(profile, crf));
+    args.extend(audio_args(profile));
+
+    let pb = ProgressBar::new(0);
+    pb.set_style(encode_progress_style()?);
+    pb.set_message(format!("Assembling {} clips", inputs.len()));
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let result = run_ffmpeg_with_progress(&args, output, Some(total_sec), &pb);
+    pb.finish_and_clear();
+
+    result.context("failed to assemble the final video")
+}
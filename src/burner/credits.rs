@@ -1,27 +1,65 @@
-use anyhow::{Context, Result};
-use indicatif::{ProgressBar, ProgressStyle};
-use std::borrow::Cow;
+use anyhow::Result;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::BufReader;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
-use std::io::Read;
+use crate::burner::utils::{
+    anchored_x_expr, anchored_y_expr, audio_source_args, drawtext_style, encode_progress_style,
+    escape_text, get_video_duration_sec, has_audio_stream, run_ffmpeg_with_progress,
+};
+use crate::download::{USER_INFO_FILE_NAME, UserInfo, find_avatar, is_partial_download};
+
+use crate::burner::animation::{LinePosition, OVERLAY_SIZES, alpha_at, position_in};
+use crate::burner::encoding::{audio_args, loudnorm_filter, scaled_style, video_args};
+use crate::burner::framing::framing_filter;
+use crate::burner::slides::{FOREVER_SEC, TextSlide, slides_filter};
+use crate::clip_settings::{ClipSettings, Trim};
+use crate::config::{
+    Animation, Config, CreditMode, CreditsConfig, EncodingProfile, FramingConfig, StyleConfig,
+};
+use crate::media_cache::MediaInfo;
+use crate::order::sort_clip_files;
+
+/// what's already known about the clips of a video, so burning doesn't have to probe them again
+#[derive(Debug, Default)]
+pub struct ProbedClips {
+    /// probes of the downloaded clips, by path
+    pub inputs: HashMap<PathBuf, MediaInfo>,
+    /// burned clips that are complete videos, which don't need to be burned again
+    pub complete_outputs: HashSet<PathBuf>,
+}
 
-use crate::burner::utils::escape_text;
-use crate::download::UserInfo;
+/// burned files smaller than this are treated as leftovers of an interrupted encode
+const MIN_BURNED_SIZE_BYTES: u64 = 1024 * 100;
 
-use crate::burner::consts::{
-    BASE_SCALE_FILTER, FONT_SIZE, LINE_SPACING, PADDING_BOTTOM, PADDING_RIGHT,
-};
+/// whether a probed burned file is a complete video
+pub const fn is_complete_burn(info: &MediaInfo) -> bool {
+    info.stamp.size >= MIN_BURNED_SIZE_BYTES
+}
 
 pub struct EncodeTask {
     pub input: PathBuf,
     pub output: PathBuf,
     pub user_info: UserInfo<'static>,
+    /// how the clip is fitted into the frame, with the clip's own settings applied
+    pub framing: FramingConfig,
+    /// the cached probe of the input (with its loudness, if that's being normalised)
+    pub media: Option<MediaInfo>,
+    /// the part of the input to keep, from the clip's settings
+    pub trim: Option<Trim>,
+    /// the creator's avatar, drawn next to the credit if there is one
+    pub avatar: Option<PathBuf>,
 }
 
-fn collect_tasks(base_folder: &Path) -> Result<Vec<EncodeTask>> {
+fn collect_tasks(
+    base_folder: &Path,
+    config: &Config,
+    probed: &ProbedClips,
+) -> Result<Vec<EncodeTask>> {
     let mut tasks = Vec::new();
 
     for user_entry in fs::read_dir(base_folder)? {
@@ -31,7 +69,7 @@ fn collect_tasks(base_folder: &Path) -> Result<Vec<EncodeTask>> {
             continue;
         }
 
-        let info_path = user_path.join("user_info.toml");
+        let info_path = user_path.join(USER_INFO_FILE_NAME);
         let video_folder = user_path.join("video");
 
         if !info_path.exists() || !video_folder.exists() {
@@ -46,10 +84,13 @@ fn collect_tasks(base_folder: &Path) -> Result<Vec<EncodeTask>> {
             continue;
         };
 
-        let owned_user_info = UserInfo {
-            user_id: Cow::Owned(user_info.user_id.into_owned()),
-            display_name: Cow::Owned(user_info.display_name.into_owned()),
-            username: Cow::Owned(user_info.username.into_owned()),
+        let owned_user_info = user_info.into_owned();
+        let settings = ClipSettings::load(&user_path)?;
+        let framing = settings.framing(&config.framing);
+        let avatar = if config.credits.avatar {
+            find_avatar(&user_path)
+        } else {
+            None
         };
 
         let burned_dir = user_path.join("burned");
@@ -63,7 +104,7 @@ fn collect_tasks(base_folder: &Path) -> Result<Vec<EncodeTask>> {
         for video_entry in fs::read_dir(&video_folder)? {
             let video_path = video_entry?.path();
 
-            if !video_path.is_file() {
+            if !video_path.is_file() || is_partial_download(&video_path) {
                 continue;
             }
 
@@ -72,140 +113,316 @@ fn collect_tasks(base_folder: &Path) -> Result<Vec<EncodeTask>> {
             };
             let output_video = burned_dir.join(filename);
 
-            if !is_video_valid(&output_video) {
+            if !probed.complete_outputs.contains(&output_video) {
                 tasks.push(EncodeTask {
+                    media: probed.inputs.get(&video_path).cloned(),
                     input: video_path,
                     output: output_video,
                     user_info: owned_user_info.clone(),
+                    framing: framing.clone(),
+                    trim: settings.trim,
+                    avatar: avatar.clone(),
                 });
             }
         }
     }
 
-    Ok(tasks)
-}
-
-fn is_video_valid(path: &PathBuf) -> bool {
-    // File must be reasonably sized (avoid partial writes)
-    const MIN_SIZE_BYTES: u64 = 1024 * 100; // 100KB
-    if !path.exists() {
-        return false;
-    }
-
-    if let Ok(metadata) = fs::metadata(path) {
-        if metadata.len() < MIN_SIZE_BYTES {
-            return false;
-        }
-    } else {
-        return false;
-    }
+    // encode in running order, so the first clips are ready to look at first
+    sort_clip_files(base_folder, &mut tasks, |task| task.input.as_path())?;
 
-    // Use ffprobe to verify container + streams
-    let status = Command::new("ffprobe")
-        .args([
-            "-v",
-            "error",
-            "-show_entries",
-            "stream=codec_type",
-            "-of",
-            "csv=p=0",
-            path.to_string_lossy().as_ref(),
-        ])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status();
-
-    matches!(status, Ok(s) if s.success())
+    Ok(tasks)
 }
 
-pub fn run_ffmpeg(task: &EncodeTask, font_file: &Path, crf: Option<i32>) -> Result<()> {
+/// the credit as a single drawtext filter that's on screen for the whole clip
+fn static_credit_filter(task: &EncodeTask, font_path: &str, style: &StyleConfig) -> String {
     let raw_text = format!(
         "{}\n{}",
         task.user_info.display_name, task.user_info.username
     );
     let escaped_text = escape_text(&raw_text);
 
-    let font_path = font_file.to_string_lossy().replace('\\', "\\\\");
+    let text_style = drawtext_style(style);
+    let x_expr = anchored_x_expr(style);
+    let y_expr = anchored_y_expr(style);
+    let line_spacing = style.line_spacing;
+    // 1 and 2 are ffmpeg's left and right alignment flags
+    let text_align = if style.anchor.is_left() { 1 } else { 2 };
 
-    let drawtext_filter = format!(
+    format!(
         "drawtext=\
 fontfile='{font_path}':\
 text='{escaped_text}':\
-fontcolor=white@0.75:\
-fontsize={FONT_SIZE}:\
-x=w-(tw+{PADDING_RIGHT}):\
-y=h-(th+{PADDING_BOTTOM}):\
-line_spacing={LINE_SPACING}:\
-text_align=2"
-    );
+{text_style}:\
+x={x_expr}:\
+y={y_expr}:\
+line_spacing={line_spacing}:\
+text_align={text_align}"
+    )
+}
 
-    let filter_complex = format!("{BASE_SCALE_FILTER},{drawtext_filter}");
-
-    let mut args = vec![
-        "-y".to_string(),
-        "-i".to_string(),
-        task.input.to_string_lossy().into_owned(),
-        "-vf".to_string(),
-        filter_complex,
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-preset".to_string(),
-        "fast".to_string(),
-        "-pix_fmt".to_string(),
-        "yuv420p".to_string(),
-        "-movflags".to_string(),
-        "+faststart".to_string(),
-        "-r".to_string(),
-        "30".to_string(),
-        "-map".to_string(),
-        "0:v:0".to_string(),
-        "-map".to_string(),
-        "0:a?".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-b:a".to_string(),
-        "192k".to_string(),
-    ];
-
-    if let Some(crf_val) = crf {
-        args.push("-crf".to_string());
-        args.push(crf_val.to_string());
-    }
+/// when the animated credit starts entering, and when it has to be gone by
+///
+/// `duration_sec` is the length of the (trimmed) clip, the credit is gone by the time it ends
+fn credit_window(credits: &CreditsConfig, duration_sec: Option<f64>) -> (f64, Option<f64>) {
+    let start_sec = credits.delay;
+    let end_sec = match (credits.duration, duration_sec) {
+        (Some(on_screen), Some(clip)) => Some((start_sec + on_screen).min(clip)),
+        (Some(on_screen), None) => Some(start_sec + on_screen),
+        (None, clip) => clip,
+    };
+
+    (start_sec, end_sec)
+}
 
-    args.push(task.output.to_string_lossy().into_owned());
+/// the credit sliding in and out like the intro/outro text
+fn animated_credit_filter(
+    task: &EncodeTask,
+    font_path: &str,
+    style: &StyleConfig,
+    credits: &CreditsConfig,
+    animation: &Animation,
+    duration_sec: Option<f64>,
+) -> String {
+    let (start_sec, end_sec) = credit_window(credits, duration_sec);
+
+    let slide = TextSlide {
+        lines: vec![&task.user_info.display_name, &task.user_info.username],
+        start_sec,
+        end_sec,
+        already_visible: false,
+    };
+
+    slides_filter(&[slide], font_path, style, animation)
+}
+
+/// the avatar is as tall as both lines of the credit
+const fn avatar_size(style: &StyleConfig) -> u32 {
+    style.font_size * 2 + style.line_spacing
+}
 
-    let mut child = Command::new("ffmpeg")
-        .args(&args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn()
-        .context("Failed to spawn ffmpeg")?;
+/// the credit text moved away from the anchored edge, leaving room for the avatar
+fn style_beside_avatar(style: &StyleConfig) -> StyleConfig {
+    StyleConfig {
+        padding_x: style.padding_x + avatar_size(style) + style.font_size / 2,
+        ..style.clone()
+    }
+}
 
-    let mut stderr_output = String::new();
-    if let Some(mut stderr) = child.stderr.take() {
-        BufReader::new(&mut stderr).read_to_string(&mut stderr_output)?;
+/// how the avatar moves along with the animated credit, entering with its top line and leaving
+/// with its bottom one. `None` for static credits
+fn avatar_animation(config: &Config, duration_sec: Option<f64>) -> Option<(Animation, f64, f64)> {
+    if config.credits.mode == CreditMode::Static {
+        return None;
     }
 
-    let status = child.wait()?;
+    let animation = config.animation.animation();
+    let (start_sec, end_sec) = credit_window(&config.credits, duration_sec);
+    let t_out = end_sec.map_or(FOREVER_SEC, |end| {
+        (end - animation.exit.length_sec()).max(start_sec + animation.enter.length_sec())
+    });
+
+    Some((animation, start_sec, t_out))
+}
+
+/// cuts input `input_index` into a circle and lays it over `[credit_base]` against the anchored
+/// edge, where [`style_beside_avatar`] left room for it
+fn avatar_overlay_filter(
+    input_index: usize,
+    style: &StyleConfig,
+    motion: Option<&(Animation, f64, f64)>,
+) -> String {
+    let size = avatar_size(style);
+    let base_x = if style.anchor.is_left() {
+        style.padding_x.to_string()
+    } else {
+        format!("W-w-{}", style.padding_x)
+    };
+    let base_y = if style.anchor.is_top() {
+        style.padding_y.to_string()
+    } else {
+        format!("H-h-{}", style.padding_y)
+    };
+
+    // geq calls the time `T`, overlay calls it `t` like drawtext
+    let (visibility, LinePosition { x, y }) = match motion {
+        Some((animation, t_in, t_out)) => (
+            alpha_at("T", animation, *t_in, *t_out),
+            position_in(
+                &OVERLAY_SIZES,
+                animation,
+                style,
+                &base_x,
+                &base_y,
+                *t_in,
+                *t_out,
+            ),
+        ),
+        None => (
+            "1".to_string(),
+            LinePosition {
+                x: base_x,
+                y: base_y,
+            },
+        ),
+    };
+
+    format!(
+        "[{input_index}:v]scale={size}:{size}:force_original_aspect_ratio=increase,\
+crop={size}:{size},format=rgba,\
+geq=r='r(X,Y)':g='g(X,Y)':b='b(X,Y)':a='alpha(X,Y)*lte(hypot(X-W/2,Y-H/2),W/2)*({visibility})'\
+[credit_avatar];\
+[credit_base][credit_avatar]overlay=x='{x}':y='{y}':shortest=1[credit_out]"
+    )
+}
+
+pub fn run_ffmpeg(
+    task: &EncodeTask,
+    config: &Config,
+    profile: &EncodingProfile,
+    crf: Option<i32>,
+    pb: &ProgressBar,
+) -> Result<()> {
+    let Some(avatar) = &task.avatar else {
+        return encode_clip(task, None, config, profile, crf, pb);
+    };
+
+    // the avatar is only a nice extra, a file ffmpeg can't use shouldn't cost the whole clip
+    encode_clip(task, Some(avatar), config, profile, crf, pb).or_else(|e| {
+        pb.println(format!(
+            "warning: failed to burn {} with the avatar, burning it without: {e:#}",
+            task.input.display()
+        ));
+        encode_clip(task, None, config, profile, crf, pb)
+    })
+}
+
+fn encode_clip(
+    task: &EncodeTask,
+    avatar: Option<&Path>,
+    config: &Config,
+    profile: &EncodingProfile,
+    crf: Option<i32>,
+    pb: &ProgressBar,
+) -> Result<()> {
+    let font_path = config.fs.font_file.to_string_lossy().replace('\\', "\\\\");
+    let style = &scaled_style(&config.style, profile);
+
+    let duration_sec = task
+        .media
+        .as_ref()
+        .map(|media| media.duration_sec)
+        .or_else(|| get_video_duration_sec(&task.input).ok())
+        .map(|duration| {
+            task.trim
+                .map_or(duration, |trim| trim.duration_sec(duration))
+        });
+
+    let text_style = if avatar.is_some() {
+        &style_beside_avatar(style)
+    } else {
+        style
+    };
+
+    let drawtext_filter = match config.credits.mode {
+        CreditMode::Static => static_credit_filter(task, &font_path, text_style),
+        CreditMode::Animated => animated_credit_filter(
+            task,
+            &font_path,
+            text_style,
+            &config.credits,
+            &config.animation.animation(),
+            duration_sec,
+        ),
+    };
+
+    let video_filter = format!(
+        "{},{drawtext_filter}",
+        framing_filter(&task.framing, profile)
+    );
 
-    if !status.success() {
-        let error_log_path = task.output.with_extension("mp4.error.log");
-        fs::write(&error_log_path, stderr_output)?;
-        anyhow::bail!("FFmpeg exited with non-zero status");
+    let has_audio = match &task.media {
+        Some(media) => media.has_audio(),
+        None => has_audio_stream(&task.input)?,
+    };
+    let (audio_inputs, audio_outputs) = audio_source_args(has_audio);
+
+    // seeking before `-i` resets the timestamps, so the credit still shows up at the start of
+    // what's left of the clip
+    let mut args = vec!["-y".to_string()];
+    if let Some(trim) = &task.trim {
+        args.extend(trim.input_args());
+    }
+    args.extend(["-i".to_string(), task.input.to_string_lossy().into_owned()]);
+    // the avatar comes after the silent audio track, which has to come right after the clip
+    let avatar_index = 1 + usize::from(!audio_inputs.is_empty());
+    args.extend(audio_inputs);
+
+    if let Some(avatar) = avatar {
+        let motion = avatar_animation(config, duration_sec);
+        args.extend([
+            // `-loop` only exists for still images, this also works for animated avatars
+            "-stream_loop".to_string(),
+            "-1".to_string(),
+            "-i".to_string(),
+            avatar.to_string_lossy().into_owned(),
+            "-filter_complex".to_string(),
+            format!(
+                "[0:v]{video_filter}[credit_base];{}",
+                avatar_overlay_filter(avatar_index, style, motion.as_ref())
+            ),
+            "-map".to_string(),
+            "[credit_out]".to_string(),
+        ]);
+    } else {
+        args.extend([
+            "-vf".to_string(),
+            video_filter,
+            "-map".to_string(),
+            "0:v:0".to_string(),
+        ]);
     }
+    args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    args.extend(audio_outputs);
+    if config.loudness.enabled
+        && let Some(filter) = task
+            .media
+            .as_ref()
+            .and_then(|media| media.loudness_for(task.trim))
+            .and_then(|measured| loudnorm_filter(measured, &config.loudness))
+    {
+        args.extend(["-af".to_string(), filter]);
+    }
+    args.extend(video_args(profile, crf));
+    args.extend(audio_args(profile));
 
-    Ok(())
+    run_ffmpeg_with_progress(&args, &task.output, duration_sec, pb)
+}
+
+/// how many clips to encode at once when `--jobs` isn't given
+///
+/// x264 already spreads a single encode over a few threads, so one job per four cores keeps
+/// the machine busy without having every encode fight over the same cores
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |cores| (cores.get() / 4).max(1))
 }
 
-pub fn burn_credits(base_folder: &Path, font_file: &Path, crf: Option<i32>) -> Result<()> {
-    let tasks = collect_tasks(base_folder)?;
+pub fn burn_credits(
+    base_folder: &Path,
+    config: &Config,
+    profile: &EncodingProfile,
+    crf: Option<i32>,
+    jobs: usize,
+    probed: &ProbedClips,
+) -> Result<()> {
+    let tasks = collect_tasks(base_folder, config, probed)?;
 
     if tasks.is_empty() {
         println!("No videos to process.");
         return Ok(());
     }
 
-    let pb = ProgressBar::new(tasks.len() as u64);
+    let multi = MultiProgress::new();
+    let pb = multi.add(ProgressBar::new(tasks.len() as u64));
     pb.set_style(
         ProgressStyle::with_template(
             "[{elapsed_precise}] [{bar:40.green/blue}] {pos}/{len} ({eta}) {msg}",
@@ -213,20 +430,45 @@ pub fn burn_credits(base_folder: &Path, font_file: &Path, crf: Option<i32>) -> R
         .progress_chars("##-"),
     );
 
-    for task in tasks {
-        let filename = task.input.file_name().map_or_else(
-            || "Unknown".to_string(),
-            |n| n.to_string_lossy().into_owned(),
-        );
+    let job_style = encode_progress_style()?;
+    let jobs = jobs.clamp(1, tasks.len());
+    let queue = Mutex::new(tasks.into_iter());
 
-        pb.set_message(format!("Encoding {filename}"));
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let job_pb = multi.add(ProgressBar::new(0));
+            job_pb.set_style(job_style.clone());
+            job_pb.enable_steady_tick(Duration::from_millis(100));
 
-        if let Err(e) = run_ffmpeg(&task, font_file, crf) {
-            eprintln!("Failed to process '{}': {e}", task.input.display());
-        }
+            let (queue, multi, pb) = (&queue, &multi, &pb);
 
-        pb.inc(1);
-    }
+            scope.spawn(move || {
+                loop {
+                    let next = queue.lock().ok().and_then(|mut tasks| tasks.next());
+                    let Some(task) = next else {
+                        break;
+                    };
+
+                    let filename = task.input.file_name().map_or_else(
+                        || "Unknown".to_string(),
+                        |n| n.to_string_lossy().into_owned(),
+                    );
+
+                    job_pb.set_message(format!("Encoding {filename}"));
+
+                    if let Err(e) = run_ffmpeg(&task, config, profile, crf, &job_pb) {
+                        multi.suspend(|| {
+                            eprintln!("Failed to process '{}': {e}", task.input.display());
+                        });
+                    }
+
+                    pb.inc(1);
+                }
+
+                job_pb.finish_and_clear();
+            });
+        }
+    });
 
     pb.finish_with_message("All videos processed 🎬");
     Ok(())
@@ -0,0 +1,154 @@
+use super::consts::OUTPUT_SAMPLE_RATE;
+use crate::config::{
+    BorderStyle, BoxStyle, EncodingProfile, LoudnessConfig, ShadowStyle, StyleConfig, VideoCodec,
+};
+use crate::probe::LoudnessMeasurement;
+
+/// the height the sizes in `[style]` are meant for, they're scaled for other resolutions
+const STYLE_REFERENCE_HEIGHT: u32 = 1080;
+
+/// ffmpeg arguments that encode the video stream with `profile`
+///
+/// `crf` (from `--crf`) takes priority over whatever quality the profile asks for
+pub fn video_args(profile: &EncodingProfile, crf: Option<i32>) -> Vec<String> {
+    let mut args = vec![
+        "-c:v".to_string(),
+        profile.codec.encoder().to_string(),
+        "-preset".to_string(),
+        profile.preset().to_string(),
+        "-pix_fmt".to_string(),
+        profile.pix_fmt.clone(),
+        "-r".to_string(),
+        profile.fps.to_string(),
+    ];
+
+    if let Some(crf) = crf
+        .map(|crf| crf.to_string())
+        .or_else(|| profile.crf.map(|crf| crf.to_string()))
+    {
+        args.extend(["-crf".to_string(), crf]);
+    } else if let Some(bitrate) = &profile.bitrate {
+        args.extend(["-b:v".to_string(), bitrate.clone()]);
+    }
+
+    // quicktime (and so most apple devices) only plays hevc tagged like this
+    if profile.codec == VideoCodec::X265 {
+        args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+    }
+
+    args
+}
+
+/// ffmpeg arguments that encode the audio stream with `profile`
+///
+/// the audio is always stereo at the same sample rate, so every output has the same layout
+pub fn audio_args(profile: &EncodingProfile) -> Vec<String> {
+    vec![
+        "-c:a".to_string(),
+        profile.audio_codec.clone(),
+        "-b:a".to_string(),
+        profile.audio_bitrate.clone(),
+        "-ar".to_string(),
+        OUTPUT_SAMPLE_RATE.to_string(),
+        "-ac".to_string(),
+        "2".to_string(),
+    ]
+}
+
+/// second pass of the loudness normalisation, using what the analysis pass measured
+///
+/// `None` for clips that are silent, since there is nothing to normalise
+pub fn loudnorm_filter(measured: &LoudnessMeasurement, target: &LoudnessConfig) -> Option<String> {
+    if !measured.integrated.is_finite() || !measured.threshold.is_finite() {
+        return None;
+    }
+
+    // loudnorm works at 192kHz internally, the `-ar` from `audio_args` brings it back down
+    Some(format!(
+        "loudnorm=I={}:TP={}:LRA={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:linear=true",
+        target.target_lufs,
+        target.true_peak,
+        target.range,
+        measured.integrated,
+        measured.true_peak,
+        measured.range,
+        measured.threshold,
+    ))
+}
+
+/// the configured style with every size scaled to the resolution of `profile`
+pub fn scaled_style(style: &StyleConfig, profile: &EncodingProfile) -> StyleConfig {
+    let scale = |value: u32| {
+        u32::try_from(
+            u64::from(value) * u64::from(profile.height) / u64::from(STYLE_REFERENCE_HEIGHT),
+        )
+        .unwrap_or(value)
+    };
+    let scale_signed = |value: i32| {
+        i32::try_from(
+            i64::from(value) * i64::from(profile.height) / i64::from(STYLE_REFERENCE_HEIGHT),
+        )
+        .unwrap_or(value)
+    };
+
+    StyleConfig {
+        font_size: scale(style.font_size),
+        line_spacing: scale(style.line_spacing),
+        padding_x: scale(style.padding_x),
+        padding_y: scale(style.padding_y),
+        shadow: style.shadow.as_ref().map(|shadow| ShadowStyle {
+            x: scale_signed(shadow.x),
+            y: scale_signed(shadow.y),
+            ..shadow.clone()
+        }),
+        border: style.border.as_ref().map(|border| BorderStyle {
+            width: scale(border.width),
+            ..border.clone()
+        }),
+        text_box: style.text_box.as_ref().map(|text_box| BoxStyle {
+            padding: scale(text_box.padding),
+            ..text_box.clone()
+        }),
+        ..style.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+
+    use super::loudnorm_filter;
+    use crate::config::LoudnessConfig;
+    use crate::probe::LoudnessMeasurement;
+
+    const MEASURED: LoudnessMeasurement = LoudnessMeasurement {
+        integrated: -23.5,
+        true_peak: -4.2,
+        range: 6.7,
+        threshold: -33.8,
+    };
+
+    #[test]
+    fn passes_the_measurement_to_the_second_pass() -> Result<()> {
+        let filter = loudnorm_filter(&MEASURED, &LoudnessConfig::default())
+            .context("a measured clip should be normalised")?;
+
+        assert!(filter.starts_with("loudnorm=I=-14:TP=-1:LRA=11:"));
+        assert!(filter.contains("measured_I=-23.5:measured_TP=-4.2:measured_LRA=6.7"));
+        assert!(filter.contains("measured_thresh=-33.8"));
+        assert!(filter.ends_with(":linear=true"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn silent_clips_are_left_alone() {
+        let silent = LoudnessMeasurement {
+            integrated: f64::NEG_INFINITY,
+            threshold: f64::NEG_INFINITY,
+            ..MEASURED
+        };
+
+        assert_eq!(loudnorm_filter(&silent, &LoudnessConfig::default()), None);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::config::{EncodingProfile, FramingConfig, FramingMode};
+
+/// filter that fits a single video stream into the frame of `profile`
+///
+/// the result can be used on its own with `-vf`, or have more filters chained after it
+pub fn framing_filter(framing: &FramingConfig, profile: &EncodingProfile) -> String {
+    let (w, h) = (profile.width, profile.height);
+
+    match framing.mode {
+        FramingMode::Pad => format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,\
+pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:color={},setsar=1",
+            framing.color
+        ),
+        FramingMode::CropFill => {
+            format!("scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},setsar=1")
+        }
+        // the background is blurred at a quarter of the size, which looks the same and is a lot
+        // faster than blurring the full frame
+        FramingMode::BlurFill => {
+            let (small_w, small_h) = (w / 4, h / 4);
+            let sigma = f64::from(framing.blur) / 4.0;
+
+            format!(
+                "split[framing_bg][framing_fg];\
+[framing_bg]scale={small_w}:{small_h}:force_original_aspect_ratio=increase,\
+crop={small_w}:{small_h},gblur=sigma={sigma},scale={w}:{h},setsar=1[framing_blurred];\
+[framing_fg]scale={w}:{h}:force_original_aspect_ratio=decrease,setsar=1[framing_clip];\
+[framing_blurred][framing_clip]overlay=(W-w)/2:(H-h)/2"
+            )
+        }
+    }
+}
@@ -1,19 +1,17 @@
 use anyhow::Result;
 use clap::Args;
-use futures_util::StreamExt;
-use std::borrow::Cow;
 use std::path::PathBuf;
 use tempfile::tempdir;
-use tokio::io::AsyncWriteExt;
 
-use super::consts::{
-    BASE_SCALE_FILTER, FONT_SIZE, INTRO_LINE_1, INTRO_LINE_2, LINE_SPACING, LINE_STAGGER,
-    PADDING_BOTTOM, SLIDE_DUR, SWITCH_TIME,
-};
-use super::utils::{generate_line_filter, run_ffmpeg_filter};
+use super::consts::SWITCH_TIME;
+use super::encoding::scaled_style;
+use super::framing::framing_filter;
+use super::slides::{SlideArgs, TextSlide, slides_filter};
+use super::utils::run_ffmpeg_filter;
 use crate::api::client::ApiClient;
+use crate::clip_settings::ClipSettings;
 use crate::config::Config;
-use crate::download::UserInfo;
+use crate::download::{UserInfo, download_file_into_temp_dir};
 
 #[derive(Args, Debug, Clone)]
 pub struct IntroTextArgs {
@@ -21,6 +19,11 @@ pub struct IntroTextArgs {
     pub clip_id: String,
     #[arg(short, long)]
     pub output: PathBuf,
+    /// encoding profile to use (defaults to `default_profile` from the config)
+    #[arg(long)]
+    pub profile: Option<String>,
+    #[command(flatten)]
+    pub slides: SlideArgs,
 }
 
 pub async fn process_intro_text(
@@ -28,95 +31,57 @@ pub async fn process_intro_text(
     config: &Config,
     api_client: &ApiClient,
 ) -> Result<()> {
+    let profile = config.profile(args.profile.as_deref())?;
     let temp_dir = tempdir()?;
     let res = api_client.get_single_clip(&args.clip_id).await?;
 
-    let user_info = res.clip.overridden_profile_data.as_ref().map_or_else(
-        || UserInfo {
-            user_id: Cow::Owned(res.clip.creator.id.clone()),
-            display_name: Cow::Owned(res.clip.creator.name.clone()),
-            username: Cow::Owned(format!("@{}", res.clip.creator.username)),
-        },
-        |profile| UserInfo {
-            user_id: Cow::Owned(format!("profile_{}", profile.id)),
-            display_name: Cow::Owned(profile.line1.clone()),
-            username: Cow::Owned(profile.line2.clone()),
-        },
-    );
+    let user_info = UserInfo::from_clip(&res.clip);
+    let settings = ClipSettings::load_for_clip(config, &res.clip.video_id, &res.clip.id)?;
 
     let font_path_str = config.fs.font_file.to_string_lossy().replace('\\', "\\\\");
-    let y_bottom = format!("h-({FONT_SIZE}+{PADDING_BOTTOM})");
-    let y_top = format!("h-({FONT_SIZE}*2+{PADDING_BOTTOM}+{LINE_SPACING})");
-
     let switch_sec = SWITCH_TIME.as_secs_f64();
-    let slide_sec = SLIDE_DUR.as_secs_f64();
-    let stagger_sec = LINE_STAGGER.as_secs_f64();
-
-    let text_1_line_1_in = 0.0;
-    let text_1_line_1_out = switch_sec - slide_sec - stagger_sec;
-    let text_1_line_2_in = stagger_sec;
-    let text_1_line_2_out = switch_sec - slide_sec;
-
-    let text_2_line_1_in = switch_sec;
-    let text_2_line_2_in = switch_sec + stagger_sec;
-    let duration_max = 99999.0;
-
-    let filter_text_1_line_1 = generate_line_filter(
-        INTRO_LINE_1,
-        &font_path_str,
-        &y_top,
-        text_1_line_1_in,
-        text_1_line_1_out,
-        &format!("between(t,0,{switch_sec})"),
-    );
-
-    let filter_text_1_line_2 = generate_line_filter(
-        INTRO_LINE_2,
-        &font_path_str,
-        &y_bottom,
-        text_1_line_2_in,
-        text_1_line_2_out,
-        &format!("between(t,0,{switch_sec})"),
-    );
 
-    let filter_text_2_line_1 = generate_line_filter(
-        &user_info.display_name,
-        &font_path_str,
-        &y_top,
-        text_2_line_1_in,
-        duration_max,
-        &format!("gt(t,{text_2_line_1_in})"),
-    );
+    // every configured slide gets its turn, then the credit for the clip stays until the end
+    let text_slides = args.slides.resolve(&config.intro.slides);
+    let mut start_sec = 0.0;
+    let mut slides = Vec::with_capacity(text_slides.len() + 1);
+
+    for lines in &text_slides {
+        slides.push(TextSlide {
+            lines: lines.iter().map(String::as_str).collect(),
+            start_sec,
+            end_sec: Some(start_sec + switch_sec),
+            already_visible: false,
+        });
+        start_sec += switch_sec;
+    }
 
-    let filter_text_2_line_2 = generate_line_filter(
-        &user_info.username,
-        &font_path_str,
-        &y_bottom,
-        text_2_line_2_in,
-        duration_max,
-        &format!("gt(t,{text_2_line_2_in})"),
-    );
+    slides.push(TextSlide {
+        lines: vec![&user_info.display_name, &user_info.username],
+        start_sec,
+        end_sec: None,
+        already_visible: false,
+    });
 
+    let framing = settings.framing(&config.framing);
     let drawtext_and_scale_filter = format!(
-        "{BASE_SCALE_FILTER},{filter_text_1_line_1},{filter_text_1_line_2},{filter_text_2_line_1},{filter_text_2_line_2}"
+        "{},{}",
+        framing_filter(&framing, &profile),
+        slides_filter(
+            &slides,
+            &font_path_str,
+            &scaled_style(&config.style, &profile),
+            &config.animation.animation()
+        )
     );
 
-    let video_path = temp_dir.path().join(format!("{}.mp4", args.clip_id));
-
-    let response = api_client
-        .client
-        .get(res.clip.url)
-        .send()
-        .await?
-        .error_for_status()?;
-
-    let mut file = tokio::fs::File::create(&video_path).await?;
-
-    let mut stream = response.bytes_stream();
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk?;
-        file.write_all(&chunk).await?;
-    }
+    let video_path = download_file_into_temp_dir(&res.clip, &temp_dir, &api_client.client).await?;
 
-    run_ffmpeg_filter(&video_path, &args.output, &drawtext_and_scale_filter)
+    run_ffmpeg_filter(
+        &video_path,
+        &args.output,
+        &drawtext_and_scale_filter,
+        &profile,
+        settings.trim.as_ref(),
+    )
 }
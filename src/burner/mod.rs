@@ -1,5 +1,10 @@
+pub mod animation;
+pub mod assemble;
 pub mod consts;
 pub mod credits;
+pub mod encoding;
+pub mod framing;
 pub mod intro_text;
 pub mod outro_text;
-mod utils;
+pub mod slides;
+pub mod utils;
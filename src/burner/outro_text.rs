@@ -1,15 +1,15 @@
 use anyhow::Result;
 use clap::Args;
-use std::borrow::Cow;
 use std::path::PathBuf;
 use tempfile::tempdir;
 
-use super::consts::{
-    BASE_SCALE_FILTER, FONT_SIZE, LINE_SPACING, LINE_STAGGER, OUTRO_LINE_1, OUTRO_LINE_2,
-    PADDING_BOTTOM, SLIDE_DUR, SWITCH_TIME,
-};
-use super::utils::{generate_line_filter, get_video_duration_sec, run_ffmpeg_filter};
+use super::consts::SWITCH_TIME;
+use super::encoding::scaled_style;
+use super::framing::framing_filter;
+use super::slides::{SlideArgs, TextSlide, slides_filter};
+use super::utils::{get_video_duration_sec, run_ffmpeg_filter};
 use crate::api::client::ApiClient;
+use crate::clip_settings::ClipSettings;
 use crate::config::Config;
 use crate::download::{UserInfo, download_file_into_temp_dir};
 
@@ -22,6 +22,11 @@ pub struct OutroTextArgs {
     /// optional seconds of padding before the video ends to trigger the outro sequence
     #[arg(long, default_value_t = 0.0)]
     pub padding: f64,
+    /// encoding profile to use (defaults to `default_profile` from the config)
+    #[arg(long)]
+    pub profile: Option<String>,
+    #[command(flatten)]
+    pub slides: SlideArgs,
 }
 
 pub async fn process_outro_text(
@@ -29,89 +34,73 @@ pub async fn process_outro_text(
     config: &Config,
     api_client: &ApiClient,
 ) -> Result<()> {
+    let profile = config.profile(args.profile.as_deref())?;
     let temp_dir = tempdir()?;
     let res = api_client.get_single_clip(&args.clip_id).await?;
 
-    let user_info = res.clip.overridden_profile_data.as_ref().map_or_else(
-        || UserInfo {
-            user_id: Cow::Owned(res.clip.creator.id.clone()),
-            display_name: Cow::Owned(res.clip.creator.name.clone()),
-            username: Cow::Owned(format!("@{}", res.clip.creator.username)),
-        },
-        |profile| UserInfo {
-            user_id: Cow::Owned(format!("profile_{}", profile.id)),
-            display_name: Cow::Owned(profile.line1.clone()),
-            username: Cow::Owned(profile.line2.clone()),
-        },
-    );
+    let user_info = UserInfo::from_clip(&res.clip);
 
-    let video_path =
-        download_file_into_temp_dir(&res.clip.url, &temp_dir, &api_client.client).await?;
+    let video_path = download_file_into_temp_dir(&res.clip, &temp_dir, &api_client.client).await?;
 
     let font_path_str = config.fs.font_file.to_string_lossy().replace('\\', "\\\\");
-    let y_bottom = format!("h-({FONT_SIZE}+{PADDING_BOTTOM})");
-    let y_top = format!("h-({FONT_SIZE}*2+{PADDING_BOTTOM}+{LINE_SPACING})");
+    let settings = ClipSettings::load_for_clip(config, &res.clip.video_id, &res.clip.id)?;
 
+    // the outro text is timed from the end of what's left of the clip after trimming
     let duration_sec = get_video_duration_sec(&video_path)?;
+    let duration_sec = settings
+        .trim
+        .map_or(duration_sec, |trim| trim.duration_sec(duration_sec));
 
     // Shift our timeline backward by the padding amount
     let end_anchor_sec = (duration_sec - args.padding).max(0.0);
-    let switch_sec = (end_anchor_sec - SWITCH_TIME.as_secs_f64()).max(0.0);
-
-    let slide_sec = SLIDE_DUR.as_secs_f64();
-    let stagger_sec = LINE_STAGGER.as_secs_f64();
-
-    let text_1_line_1_in = -slide_sec;
-    let text_1_line_2_in = -slide_sec;
-    let text_1_line_1_out = switch_sec - slide_sec - stagger_sec;
-    let text_1_line_2_out = switch_sec - slide_sec;
-
-    let text_2_line_1_in = switch_sec;
-    let text_2_line_2_in = switch_sec + stagger_sec;
-
-    let text_2_line_1_out =
-        (end_anchor_sec - slide_sec - stagger_sec).max(text_2_line_1_in + slide_sec);
-    let text_2_line_2_out = (end_anchor_sec - slide_sec).max(text_2_line_2_in + slide_sec);
-
-    let filter_text_1_line_1 = generate_line_filter(
-        &user_info.display_name,
-        &font_path_str,
-        &y_top,
-        text_1_line_1_in,
-        text_1_line_1_out,
-        &format!("between(t,0,{switch_sec})"),
-    );
-
-    let filter_text_1_line_2 = generate_line_filter(
-        &user_info.username,
-        &font_path_str,
-        &y_bottom,
-        text_1_line_2_in,
-        text_1_line_2_out,
-        &format!("between(t,0,{switch_sec})"),
-    );
-
-    let filter_text_2_line_1 = generate_line_filter(
-        OUTRO_LINE_1,
-        &font_path_str,
-        &y_top,
-        text_2_line_1_in,
-        text_2_line_1_out,
-        &format!("gt(t,{text_2_line_1_in})"),
-    );
-
-    let filter_text_2_line_2 = generate_line_filter(
-        OUTRO_LINE_2,
-        &font_path_str,
-        &y_bottom,
-        text_2_line_2_in,
-        text_2_line_2_out,
-        &format!("gt(t,{text_2_line_2_in})"),
-    );
-
+    let switch_sec = SWITCH_TIME.as_secs_f64();
+
+    // the configured slides take up the end of the clip, the credit is shown until they begin
+    let text_slides = args.slides.resolve(&config.outro.slides);
+    let slide_count = f64::from(u32::try_from(text_slides.len()).unwrap_or(u32::MAX));
+    let mut start_sec = slide_count.mul_add(-switch_sec, end_anchor_sec).max(0.0);
+
+    let mut slides = Vec::with_capacity(text_slides.len() + 1);
+    slides.push(TextSlide {
+        lines: vec![&user_info.display_name, &user_info.username],
+        start_sec: 0.0,
+        end_sec: Some(start_sec),
+        already_visible: true,
+    });
+
+    for (index, lines) in text_slides.iter().enumerate() {
+        let end_sec = if index + 1 == text_slides.len() {
+            end_anchor_sec
+        } else {
+            start_sec + switch_sec
+        };
+
+        slides.push(TextSlide {
+            lines: lines.iter().map(String::as_str).collect(),
+            start_sec,
+            end_sec: Some(end_sec),
+            already_visible: false,
+        });
+        start_sec += switch_sec;
+    }
+
+    let framing = settings.framing(&config.framing);
     let drawtext_and_scale_filter = format!(
-        "{BASE_SCALE_FILTER},{filter_text_1_line_1},{filter_text_1_line_2},{filter_text_2_line_1},{filter_text_2_line_2}"
+        "{},{}",
+        framing_filter(&framing, &profile),
+        slides_filter(
+            &slides,
+            &font_path_str,
+            &scaled_style(&config.style, &profile),
+            &config.animation.animation()
+        )
     );
 
-    run_ffmpeg_filter(&video_path, &args.output, &drawtext_and_scale_filter)
+    run_ffmpeg_filter(
+        &video_path,
+        &args.output,
+        &drawtext_and_scale_filter,
+        &profile,
+        settings.trim.as_ref(),
+    )
 }
@@ -0,0 +1,178 @@
+use clap::Args;
+
+use super::utils::{generate_line_filter, line_y_expr};
+use crate::config::{Animation, StyleConfig};
+
+/// end time used for text that never leaves
+pub const FOREVER_SEC: f64 = 99999.0;
+
+// lets a single run of `burn-intro-text`/`burn-outro-text` use different text than the config
+// (a plain comment, doc comments on flattened args end up as the about text of the command)
+#[derive(Args, Debug, Clone, Default)]
+pub struct SlideArgs {
+    /// replace the first line of the first text slide
+    #[arg(long)]
+    pub line1: Option<String>,
+
+    /// replace the second line of the first text slide
+    #[arg(long)]
+    pub line2: Option<String>,
+
+    /// replace all configured text slides, with lines separated by `|` (can be repeated)
+    #[arg(long = "slide", value_name = "LINES")]
+    pub slides: Vec<String>,
+}
+
+impl SlideArgs {
+    /// applies the overrides on top of the slides from the config
+    pub fn resolve(&self, configured: &[Vec<String>]) -> Vec<Vec<String>> {
+        let mut slides = if self.slides.is_empty() {
+            configured.to_vec()
+        } else {
+            self.slides
+                .iter()
+                .map(|slide| slide.split('|').map(|l| l.trim().to_string()).collect())
+                .collect()
+        };
+
+        let overrides = [&self.line1, &self.line2];
+        if overrides.iter().all(|line| line.is_none()) {
+            return slides;
+        }
+
+        if slides.is_empty() {
+            slides.push(Vec::new());
+        }
+
+        let first = &mut slides[0];
+        for (index, line) in overrides.into_iter().enumerate() {
+            let Some(line) = line else {
+                continue;
+            };
+
+            if first.len() <= index {
+                first.resize(index + 1, String::new());
+            }
+            first[index].clone_from(line);
+        }
+
+        slides
+    }
+}
+
+/// a group of lines shown together, animating in and out one line after the other
+pub struct TextSlide<'a> {
+    pub lines: Vec<&'a str>,
+    /// when the slide starts entering
+    pub start_sec: f64,
+    /// when the slide has to be gone for the next one, `None` if it stays until the end
+    pub end_sec: Option<f64>,
+    /// whether the slide is already fully on screen at `start_sec` instead of sliding in
+    pub already_visible: bool,
+}
+
+impl TextSlide<'_> {
+    fn filters(&self, font_path: &str, style: &StyleConfig, animation: &Animation) -> Vec<String> {
+        let enter_sec = animation.enter.length_sec();
+        let exit_sec = animation.exit.length_sec();
+        let stagger_sec = animation.stagger;
+
+        let line_count = u32::try_from(self.lines.len()).unwrap_or(u32::MAX);
+        let enable_expr = self.end_sec.map_or_else(
+            || format!("gt(t,{})", self.start_sec),
+            |end| format!("between(t,{},{end})", self.start_sec),
+        );
+
+        // top lines enter first and leave first, so the bottom line is always the last one out
+        (0..line_count)
+            .zip(&self.lines)
+            .map(|(index, text)| {
+                let lines_below = f64::from(line_count - index - 1);
+
+                let t_in = if self.already_visible {
+                    self.start_sec - enter_sec
+                } else {
+                    f64::from(index).mul_add(stagger_sec, self.start_sec)
+                };
+
+                let t_out = self.end_sec.map_or(FOREVER_SEC, |end| {
+                    (lines_below.mul_add(-stagger_sec, end - exit_sec)).max(t_in + enter_sec)
+                });
+
+                generate_line_filter(
+                    text,
+                    font_path,
+                    style,
+                    animation,
+                    &line_y_expr(style, index, line_count),
+                    (t_in, t_out),
+                    &enable_expr,
+                )
+            })
+            .collect()
+    }
+}
+
+/// the drawtext filters for a whole sequence of slides, joined into one filter chain
+pub fn slides_filter(
+    slides: &[TextSlide<'_>],
+    font_path: &str,
+    style: &StyleConfig,
+    animation: &Animation,
+) -> String {
+    slides
+        .iter()
+        .flat_map(|slide| slide.filters(font_path, style, animation))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlideArgs;
+
+    fn configured() -> Vec<Vec<String>> {
+        vec![
+            vec!["first".to_string(), "second".to_string()],
+            vec!["third".to_string()],
+        ]
+    }
+
+    #[test]
+    fn no_overrides_keeps_the_config() {
+        assert_eq!(SlideArgs::default().resolve(&configured()), configured());
+    }
+
+    #[test]
+    fn slides_replace_the_config() {
+        let args = SlideArgs {
+            slides: vec!["a | b".to_string(), "c".to_string()],
+            ..SlideArgs::default()
+        };
+
+        assert_eq!(args.resolve(&configured()), vec![vec!["a", "b"], vec!["c"]]);
+    }
+
+    #[test]
+    fn line_overrides_only_touch_the_first_slide() {
+        let args = SlideArgs {
+            line2: Some("new".to_string()),
+            ..SlideArgs::default()
+        };
+
+        assert_eq!(
+            args.resolve(&configured()),
+            vec![vec!["first", "new"], vec!["third"]]
+        );
+    }
+
+    #[test]
+    fn line_overrides_fill_in_missing_lines() {
+        let args = SlideArgs {
+            line2: Some("only".to_string()),
+            ..SlideArgs::default()
+        };
+
+        assert_eq!(args.resolve(&[]), vec![vec!["", "only"]]);
+    }
+}
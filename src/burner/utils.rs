@@ -1,10 +1,20 @@
 use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
 use std::{
+    fmt::Write as _,
+    fs,
+    io::{BufRead, BufReader, Read},
     path::Path,
     process::{Command, Stdio},
+    thread,
+    time::Duration,
 };
 
-use super::consts::{FADE_DUR, FONT_SIZE, PADDING_RIGHT, SLIDE_DUR};
+use super::animation::{LinePosition, alpha, position};
+use super::consts::OUTPUT_SAMPLE_RATE;
+use super::encoding::{audio_args, video_args};
+use crate::clip_settings::Trim;
+use crate::config::{Animation, EncodingProfile, StyleConfig};
 
 pub fn escape_text(text: &str) -> String {
     text.replace('\\', "\\\\")
@@ -14,64 +24,287 @@ pub fn escape_text(text: &str) -> String {
         .to_string()
 }
 
+/// the parts of a drawtext filter that come from the configured style (colour, size, effects)
+pub fn drawtext_style(style: &StyleConfig) -> String {
+    let mut out = format!(
+        "fontcolor={}@{}:fontsize={}",
+        style.color, style.opacity, style.font_size
+    );
+
+    if let Some(shadow) = &style.shadow {
+        let _ = write!(
+            out,
+            ":shadowcolor={}@{}:shadowx={}:shadowy={}",
+            shadow.color, shadow.opacity, shadow.x, shadow.y
+        );
+    }
+
+    if let Some(border) = &style.border {
+        let _ = write!(
+            out,
+            ":borderw={}:bordercolor={}@{}",
+            border.width, border.color, border.opacity
+        );
+    }
+
+    if let Some(text_box) = &style.text_box {
+        let _ = write!(
+            out,
+            ":box=1:boxcolor={}@{}:boxborderw={}",
+            text_box.color, text_box.opacity, text_box.padding
+        );
+    }
+
+    out
+}
+
+/// x position of a block of text resting against the anchored edge
+pub fn anchored_x_expr(style: &StyleConfig) -> String {
+    if style.anchor.is_left() {
+        style.padding_x.to_string()
+    } else {
+        format!("w-(tw+{})", style.padding_x)
+    }
+}
+
+/// y position of a block of text resting against the anchored edge
+pub fn anchored_y_expr(style: &StyleConfig) -> String {
+    if style.anchor.is_top() {
+        style.padding_y.to_string()
+    } else {
+        format!("h-(th+{})", style.padding_y)
+    }
+}
+
+/// y position of line `line` (counting from the top) out of `line_count` stacked lines, for when
+/// every line is drawn by its own drawtext filter
+pub fn line_y_expr(style: &StyleConfig, line: u32, line_count: u32) -> String {
+    let font_size = style.font_size;
+    let line_spacing = style.line_spacing;
+
+    if style.anchor.is_top() {
+        format!("{}+{line}*({font_size}+{line_spacing})", style.padding_y)
+    } else {
+        let lines_below = line_count - line - 1;
+        format!(
+            "h-({font_size}*{}+{}+{line_spacing}*{lines_below})",
+            lines_below + 1,
+            style.padding_y
+        )
+    }
+}
+
+/// a drawtext filter for a single line, animated in at `t_in_sec` and out at `t_out_sec`
 pub fn generate_line_filter(
     text: &str,
     font_path: &str,
+    style: &StyleConfig,
+    animation: &Animation,
     y_expr: &str,
-    t_in_sec: f64,
-    t_out_sec: f64,
+    (t_in_sec, t_out_sec): (f64, f64),
     enable_expr: &str,
 ) -> String {
     let escaped_text = escape_text(text);
-    let slide_sec = SLIDE_DUR.as_secs_f64();
-    let fade_sec = FADE_DUR.as_secs_f64();
 
-    let x_expr = format!(
-        "w-(tw+{PADDING_RIGHT})*((1-pow(1-min(max(t-{t_in_sec},0)/{slide_sec},1),3))-pow(min(max(t-{t_out_sec},0)/{slide_sec},1),3))"
+    let LinePosition { x, y } = position(
+        animation,
+        style,
+        &anchored_x_expr(style),
+        y_expr,
+        t_in_sec,
+        t_out_sec,
     );
+    let alpha_expr = alpha(animation, t_in_sec, t_out_sec);
 
-    let alpha_expr =
-        format!("min(max(t-{t_in_sec},0)/{fade_sec},1)-min(max(t-{t_out_sec},0)/{fade_sec},1)");
+    let style = drawtext_style(style);
 
     format!(
         "drawtext=\
         fontfile='{font_path}':\
         text='{escaped_text}':\
-        fontcolor=white@0.75:\
-        fontsize={FONT_SIZE}:\
-        x='{x_expr}':\
-        y='{y_expr}':\
+        {style}:\
+        x='{x}':\
+        y='{y}':\
         alpha='{alpha_expr}':\
         enable='{enable_expr}'"
     )
 }
 
-pub fn run_ffmpeg_filter(input: &Path, output: &Path, filter: &str) -> Result<()> {
-    let status = Command::new("ffmpeg")
-        .args([
-            "-y",
-            "-i",
-            &input.to_string_lossy(),
-            "-vf",
-            filter,
-            "-codec:a",
-            "copy",
-            &output.to_string_lossy(),
-        ])
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()
+pub fn run_ffmpeg_filter(
+    input: &Path,
+    output: &Path,
+    filter: &str,
+    profile: &EncodingProfile,
+    trim: Option<&Trim>,
+) -> Result<()> {
+    let (audio_inputs, audio_outputs) = audio_source_args(has_audio_stream(input)?);
+
+    let mut args = vec!["-y".to_string()];
+    if let Some(trim) = trim {
+        args.extend(trim.input_args());
+    }
+    args.extend(["-i".to_string(), input.to_string_lossy().into_owned()]);
+    args.extend(audio_inputs);
+    args.extend([
+        "-vf".to_string(),
+        filter.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-movflags".to_string(),
+        "+faststart".to_string(),
+    ]);
+    args.extend(audio_outputs);
+    args.extend(video_args(profile, None));
+    args.extend(audio_args(profile));
+
+    let pb = ProgressBar::new(0);
+    pb.set_style(encode_progress_style()?);
+    pb.set_message(format!("Encoding {}", output.display()));
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let duration_sec = get_video_duration_sec(input)
+        .ok()
+        .map(|duration| trim.map_or(duration, |trim| trim.duration_sec(duration)));
+
+    let result = run_ffmpeg_with_progress(&args, output, duration_sec, &pb);
+    pb.finish_and_clear();
+
+    match result {
+        Ok(()) => {
+            println!(
+                "✅ video processed successfully! saved as {}",
+                output.display()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            Err(e.context("❌ ffmpeg encountered an error and exited with a non-zero status code"))
+        }
+    }
+}
+
+/// extra inputs and output options for the audio of the input, with a silent stereo track taking
+/// its place if it doesn't have any (`has_audio`)
+///
+/// this way every burned clip has the same streams, which joining them together relies on. the
+/// inputs have to come right after the input, the outputs anywhere after every input
+pub fn audio_source_args(has_audio: bool) -> (Vec<String>, Vec<String>) {
+    if has_audio {
+        return (Vec::new(), vec!["-map".to_string(), "0:a:0".to_string()]);
+    }
+
+    let inputs = vec![
+        "-f".to_string(),
+        "lavfi".to_string(),
+        "-i".to_string(),
+        format!("anullsrc=channel_layout=stereo:sample_rate={OUTPUT_SAMPLE_RATE}"),
+    ];
+    // the silent track never ends on its own
+    let outputs = vec![
+        "-map".to_string(),
+        "1:a:0".to_string(),
+        "-shortest".to_string(),
+    ];
+
+    (inputs, outputs)
+}
+
+/// the style used for a bar driven by [`run_ffmpeg_with_progress`]
+pub fn encode_progress_style() -> Result<ProgressStyle> {
+    Ok(ProgressStyle::with_template(
+        "  {spinner:.green} {msg:30!} [{bar:30.cyan/blue}] {percent:>3}% {prefix:>6} ({eta})",
+    )?
+    .progress_chars("=>-"))
+}
+
+/// runs ffmpeg with `args` (everything except the output path), writing into `output`
+///
+/// ffmpeg is told to report its progress on stdout, which is used to move `pb` along
+/// (`duration_sec` being the length of the output) and to show the encode speed as its prefix.
+/// if ffmpeg fails, everything it printed is saved next to the output as `<output>.error.log`
+pub fn run_ffmpeg_with_progress(
+    args: &[String],
+    output: &Path,
+    duration_sec: Option<f64>,
+    pb: &ProgressBar,
+) -> Result<()> {
+    let total_us = duration_sec
+        .filter(|d| d.is_finite() && *d > 0.0)
+        .and_then(|d| u64::try_from(Duration::from_secs_f64(d).as_micros()).ok());
+
+    pb.set_length(total_us.unwrap_or(0));
+    pb.set_position(0);
+    pb.set_prefix("");
+
+    let mut child = Command::new("ffmpeg")
+        .args(args)
+        .args(["-progress", "pipe:1", "-nostats"])
+        .arg(output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
         .context("failed to spawn ffmpeg process")?;
 
-    if status.success() {
-        println!(
-            "✅ video processed successfully! saved as {}",
-            output.display()
+    // stderr has to be drained alongside stdout, otherwise ffmpeg blocks once the pipe is full
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut stderr_output = String::new();
+            let _ = stderr.read_to_string(&mut stderr_output);
+            stderr_output
+        })
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    // don't leave ffmpeg running (and writing to `output`) behind us
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(e).context("failed to read ffmpeg progress");
+                }
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            match key {
+                "out_time_us" => {
+                    if let (Some(total), Ok(out_time)) = (total_us, value.parse::<u64>()) {
+                        pb.set_position(out_time.min(total));
+                    }
+                }
+                "speed" => pb.set_prefix(value.trim().to_string()),
+                "progress" if value == "end" => {
+                    if let Some(total) = total_us {
+                        pb.set_position(total);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let status = child.wait().context("failed to wait for ffmpeg")?;
+    let stderr_output = stderr_reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    if !status.success() {
+        let mut log_name = output.file_name().unwrap_or_default().to_os_string();
+        log_name.push(".error.log");
+        let error_log_path = output.with_file_name(log_name);
+
+        fs::write(&error_log_path, stderr_output)?;
+        anyhow::bail!(
+            "FFmpeg exited with non-zero status, see {}",
+            error_log_path.display()
         );
-        Ok(())
-    } else {
-        anyhow::bail!("❌ ffmpeg encountered an error and exited with a non-zero status code");
     }
+
+    Ok(())
 }
 
 pub fn get_video_duration_sec(input: &Path) -> Result<f64> {
@@ -94,3 +327,26 @@ pub fn get_video_duration_sec(input: &Path) -> Result<f64> {
         .parse()
         .context("failed to parse video duration")
 }
+
+pub fn has_audio_stream(input: &Path) -> Result<bool> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "a",
+            "-show_entries",
+            "stream=index",
+            "-of",
+            "csv=p=0",
+            &input.to_string_lossy(),
+        ])
+        .output()
+        .context("failed to run ffprobe")?;
+
+    if !output.status.success() {
+        anyhow::bail!("ffprobe failed for {}", input.display());
+    }
+
+    Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+}
@@ -3,7 +3,11 @@ use std::path::PathBuf;
 
 use crate::{
     burner::{intro_text::IntroTextArgs, outro_text::OutroTextArgs},
-    commands::burn_single_clip::BurnSingleClipArgs,
+    commands::{
+        assemble::AssembleArgs, burn_single_clip::BurnSingleClipArgs, chapters::ChaptersArgs,
+        clip_trim::ClipTrimArgs, description::DescriptionArgs, export_timeline::ExportTimelineArgs,
+    },
+    order::OrderBy,
 };
 
 #[derive(Debug, Parser)]
@@ -21,6 +25,9 @@ pub enum Commands {
     /// perform operations on a specific video
     Video(VideoArgs),
 
+    /// perform operations on a single clip
+    Clip(ClipArgs),
+
     /// clean the `out` directory (deletes everything !)
     Clean,
 
@@ -45,6 +52,18 @@ pub struct VideoArgs {
     pub command: VideoCommands,
 }
 
+#[derive(Debug, Args)]
+pub struct ClipArgs {
+    #[command(subcommand)]
+    pub command: ClipCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ClipCommands {
+    /// set which part of the clip ends up in the video (without options, shows the current trim)
+    Trim(ClipTrimArgs),
+}
+
 #[derive(Debug, Subcommand)]
 pub enum VideoCommands {
     /// create a new video
@@ -56,6 +75,36 @@ pub enum VideoCommands {
         video_id: String,
     },
 
+    /// make the local clips match the selection on the frontend
+    ///
+    /// downloads newly selected clips and moves deselected ones into `removed/`
+    Sync {
+        /// the id of the video
+        video_id: String,
+    },
+
+    /// create or update `order.toml`, the running order of the clips
+    ///
+    /// new clips are added at the end, and the rest keep the order they already had
+    Order {
+        /// the id of the video
+        video_id: String,
+
+        /// what new clips are sorted by
+        #[arg(long, value_enum, default_value_t)]
+        by: OrderBy,
+
+        /// throw away the existing order and sort every clip again
+        #[arg(long)]
+        reset: bool,
+    },
+
+    /// move clips downloaded by older versions into the current `out` layout
+    MigrateLayout {
+        /// the id of the video
+        video_id: String,
+    },
+
     /// list all videos created in the frontend
     List,
 
@@ -71,12 +120,69 @@ pub enum VideoCommands {
         video_id: String,
     },
 
+    /// probe every downloaded clip and print a table of their properties
+    Probe {
+        /// the id of the video
+        video_id: String,
+
+        /// don't measure loudness, which needs to decode every clip
+        #[arg(long)]
+        skip_loudness: bool,
+
+        /// encoding profile to check against (defaults to `default_profile` from the config)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// check every downloaded clip for problems before burning, fails if any clip can't be used
+    Check {
+        /// the id of the video
+        video_id: String,
+
+        /// fail on warnings too, not just errors
+        #[arg(long)]
+        strict: bool,
+
+        /// encoding profile to check against (defaults to `default_profile` from the config)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// find black frames and silence at the start and end of every clip, and trim them off
+    AutoTrim {
+        /// the id of the video
+        video_id: String,
+
+        /// scan clips that were already trimmed automatically again (trims set by hand are kept)
+        #[arg(long)]
+        redetect: bool,
+    },
+
     /// burn credit information directly into the file
     BurnCredits {
         /// the id of the video
         video_id: String,
 
+        /// encoding profile to use (defaults to `default_profile` from the config)
+        #[arg(long)]
+        profile: Option<String>,
+
         #[arg(long)]
         crf: Option<i32>,
+
+        /// how many clips to encode at the same time (defaults to a quarter of the cpu cores)
+        #[arg(short, long)]
+        jobs: Option<usize>,
     },
+    /// join the burned clips (and an optional intro and outro) into the final video
+    Assemble(AssembleArgs),
+
+    /// print youtube chapters for the video, based on the running order
+    Chapters(ChaptersArgs),
+
+    /// render the video description (credits, chapters, ...) from a template
+    Description(DescriptionArgs),
+
+    /// export the clips in running order as a timeline for video editors
+    ExportTimeline(ExportTimelineArgs),
 }
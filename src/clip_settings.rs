@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, FramingConfig, FramingMode};
+
+pub const CLIP_SETTINGS_FILE_NAME: &str = "clip.toml";
+
+/// per-clip changes to the `[framing]` config, anything left out uses the global value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FramingOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<FramingMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blur: Option<u32>,
+}
+
+/// the part of a clip that ends up in the video, in seconds from the start of the file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trim {
+    #[serde(rename = "in", default, skip_serializing_if = "Option::is_none")]
+    pub in_sec: Option<f64>,
+    #[serde(rename = "out", default, skip_serializing_if = "Option::is_none")]
+    pub out_sec: Option<f64>,
+    /// set when the trim was found by `video auto-trim`, which never replaces a trim set by hand
+    #[serde(default)]
+    pub auto: bool,
+}
+
+impl Trim {
+    /// ffmpeg input options that only read the trimmed part (they go before `-i`)
+    ///
+    /// seeking the input is frame accurate since everything gets re-encoded, and it's a lot
+    /// faster than decoding everything up to the in point
+    pub fn input_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(in_sec) = self.in_sec {
+            args.extend(["-ss".to_string(), in_sec.to_string()]);
+        }
+        if let Some(out_sec) = self.out_sec {
+            args.extend(["-to".to_string(), out_sec.to_string()]);
+        }
+
+        args
+    }
+
+    /// how long the clip is after trimming, given the length of the whole file
+    pub fn duration_sec(&self, file_duration_sec: f64) -> f64 {
+        let out_sec = self
+            .out_sec
+            .unwrap_or(file_duration_sec)
+            .min(file_duration_sec);
+        (out_sec - self.in_sec.unwrap_or(0.0)).max(0.0)
+    }
+}
+
+/// settings for a single clip, kept next to it in `out/<video>/<clip id>/clip.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClipSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub framing: Option<FramingOverride>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trim: Option<Trim>,
+}
+
+impl ClipSettings {
+    pub fn path(clip_dir: &Path) -> PathBuf {
+        clip_dir.join(CLIP_SETTINGS_FILE_NAME)
+    }
+
+    /// loads the settings of a clip, or the defaults if it doesn't have any
+    pub fn load(clip_dir: &Path) -> Result<Self> {
+        let path = Self::path(clip_dir);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, clip_dir: &Path) -> Result<()> {
+        let path = Self::path(clip_dir);
+        let text = toml::to_string_pretty(self).context("failed to serialise clip settings")?;
+
+        fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// removes the burned version of a clip, which was made with its old settings
+    ///
+    /// returns whether there was one, `burn-credits` only burns clips again if there isn't
+    pub fn remove_burned(clip_dir: &Path) -> Result<bool> {
+        let burned_dir = clip_dir.join("burned");
+        if !burned_dir.is_dir() {
+            return Ok(false);
+        }
+
+        fs::remove_dir_all(&burned_dir)
+            .with_context(|| format!("failed to remove {}", burned_dir.display()))?;
+
+        Ok(true)
+    }
+
+    /// loads the settings of a clip by id, for commands that don't work on the `out` folder
+    pub fn load_for_clip(config: &Config, video_id: &str, clip_id: &str) -> Result<Self> {
+        Self::load(&config.fs.out_dir.join(video_id).join(clip_id))
+    }
+
+    /// the global framing with this clip's changes applied
+    pub fn framing(&self, global: &FramingConfig) -> FramingConfig {
+        let Some(framing) = &self.framing else {
+            return global.clone();
+        };
+
+        FramingConfig {
+            mode: framing.mode.unwrap_or(global.mode),
+            color: framing
+                .color
+                .clone()
+                .unwrap_or_else(|| global.color.clone()),
+            blur: framing.blur.unwrap_or(global.blur),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Trim;
+
+    /// checks the duration of a 10 second file with the given in and out points
+    fn assert_duration(in_sec: Option<f64>, out_sec: Option<f64>, expected: f64) {
+        let trim = Trim {
+            in_sec,
+            out_sec,
+            auto: false,
+        };
+        let duration = trim.duration_sec(10.0);
+        assert!(
+            (duration - expected).abs() < 1e-9,
+            "{trim:?} gave {duration}"
+        );
+    }
+
+    #[test]
+    fn trimmed_duration() {
+        assert_duration(None, None, 10.0);
+        assert_duration(Some(2.0), Some(7.5), 5.5);
+        assert_duration(Some(2.0), None, 8.0);
+        assert_duration(None, Some(4.0), 4.0);
+    }
+
+    #[test]
+    fn out_point_past_the_end_stops_at_the_end() {
+        assert_duration(Some(1.0), Some(30.0), 9.0);
+    }
+
+    #[test]
+    fn in_point_after_the_out_point_is_empty() {
+        assert_duration(Some(8.0), Some(3.0), 0.0);
+        assert_duration(Some(12.0), None, 0.0);
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::burner::assemble::assemble;
+use crate::clip_settings::ClipSettings;
+use crate::config::Config;
+use crate::local_clips::{LocalClip, list_local_clips};
+
+#[derive(Args, Debug, Clone)]
+pub struct AssembleArgs {
+    /// the id of the video
+    pub video_id: String,
+
+    /// processed intro clip (from `burn-intro-text`) to put before the first clip
+    #[arg(long)]
+    pub intro: Option<PathBuf>,
+
+    /// processed outro clip (from `burn-outro-text`) to put after the last clip
+    #[arg(long)]
+    pub outro: Option<PathBuf>,
+
+    /// where to save the final video (defaults to `out/<video id>/final.mp4`)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// encoding profile to use (defaults to `default_profile` from the config)
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    #[arg(long)]
+    pub crf: Option<i32>,
+}
+
+pub fn assemble_cmd(config: &Config, args: &AssembleArgs) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(&args.video_id);
+    let profile = config.profile(args.profile.as_deref())?;
+
+    let (burned, not_burned): (Vec<LocalClip>, Vec<LocalClip>) = list_local_clips(&video_dir)?
+        .into_iter()
+        .filter(|clip| clip.video.is_some() || clip.burned.is_some())
+        .partition(|clip| clip.burned.is_some());
+
+    for clip in &not_burned {
+        eprintln!(
+            "warning: {} hasn't been burned yet and will be left out (run burn-credits first)",
+            clip.dir.display()
+        );
+    }
+
+    // trims (and framing) are applied when burning, so a burned clip older than its settings
+    // doesn't have them yet
+    for clip in &burned {
+        if let Some(burned_file) = &clip.burned
+            && is_newer(&ClipSettings::path(&clip.dir), burned_file)
+        {
+            eprintln!(
+                "warning: {} changed since it was burned, delete {} and run burn-credits again to use the new settings",
+                ClipSettings::path(&clip.dir).display(),
+                burned_file.display()
+            );
+        }
+    }
+
+    let clips: Vec<PathBuf> = burned.into_iter().filter_map(|clip| clip.burned).collect();
+
+    if clips.is_empty() {
+        anyhow::bail!("no burned clips found in {}", video_dir.display());
+    }
+
+    let inputs: Vec<PathBuf> = args
+        .intro
+        .iter()
+        .cloned()
+        .chain(clips)
+        .chain(args.outro.iter().cloned())
+        .collect();
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| video_dir.join("final.mp4"));
+
+    assemble(&inputs, &output, &profile, args.crf)?;
+
+    println!("✅ final video saved as {}", output.display());
+
+    Ok(())
+}
+
+/// whether `path` was modified after `other` (false if either doesn't exist)
+fn is_newer(path: &Path, other: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    matches!((modified(path), modified(other)), (Some(a), Some(b)) if a > b)
+}
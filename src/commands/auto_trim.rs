@@ -0,0 +1,287 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use tabled::{Table, Tabled, settings::Style};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::clip_settings::{ClipSettings, Trim};
+use crate::config::Config;
+use crate::local_clips::list_local_clips;
+use crate::probe::{self, Gaps, Span};
+
+/// how close to the start/end of the clip a gap has to be to count as leading/trailing
+const EDGE_TOLERANCE_SEC: f64 = 0.1;
+/// silence shorter than this is left alone, it's probably just a pause before talking
+const MIN_SILENCE_SEC: f64 = 1.0;
+/// how much of a trimmed silence is kept, so the cut doesn't feel abrupt
+const SILENCE_MARGIN_SEC: f64 = 0.25;
+/// trims that would leave less than this are ignored, the detection probably went wrong
+const MIN_TRIMMED_LEN_SEC: f64 = 1.0;
+
+#[derive(Tabled)]
+struct TrimRow {
+    clip: String,
+    credit: String,
+    #[tabled(rename = "in")]
+    in_sec: String,
+    #[tabled(rename = "out")]
+    out_sec: String,
+    removed: String,
+}
+
+pub async fn handle(config: &Config, video_id: &str, redetect: bool) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(video_id);
+
+    if !video_dir.is_dir() {
+        anyhow::bail!(
+            "nothing has been downloaded for this video yet, run `ttcore-clip-preparer video download {video_id}` first"
+        );
+    }
+
+    let clips = list_local_clips(&video_dir)?;
+    let semaphore = Arc::new(Semaphore::new(4));
+
+    let pb = ProgressBar::new(clips.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(
+                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
+            )?
+            .progress_chars("#>-"),
+    );
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let mut set = JoinSet::new();
+    let mut skipped = 0;
+
+    for (index, clip) in clips.iter().enumerate() {
+        let settings = ClipSettings::load(&clip.dir)?;
+
+        // trims made by hand are never touched, detected ones only when asked to
+        let keep_existing = settings.trim.is_some_and(|trim| !trim.auto || !redetect);
+
+        let Some(path) = clip.video.clone().filter(|_| !keep_existing) else {
+            skipped += 1;
+            pb.inc(1);
+            continue;
+        };
+
+        let sem = Arc::clone(&semaphore);
+        let pb_task = pb.clone();
+
+        let permit = sem.acquire_owned().await.context("semaphore closed")?;
+
+        set.spawn(async move {
+            let _permit = permit;
+
+            let result = async {
+                let duration_sec = probe::duration_sec(&path).await?;
+                let gaps = probe::detect_gaps(&path, duration_sec).await?;
+
+                Ok::<_, anyhow::Error>((propose_trim(&gaps, duration_sec), duration_sec))
+            }
+            .await
+            .with_context(|| format!("failed to scan {}", path.display()));
+
+            pb_task.inc(1);
+
+            (index, result)
+        });
+    }
+
+    let mut rows = Vec::new();
+    let mut reburn = 0;
+
+    while let Some(task_result) = set.join_next().await {
+        let (index, result) = task_result.context("task panicked")?;
+        let clip = &clips[index];
+
+        let (trim, duration_sec) = match result {
+            Ok(found) => found,
+            Err(e) => {
+                pb.println(format!("{e:#}"));
+                continue;
+            }
+        };
+
+        if save_trim(&clip.dir, trim)? {
+            reburn += 1;
+        }
+
+        if let Some(trim) = trim {
+            rows.push((
+                index,
+                TrimRow {
+                    clip: clip.clip_id.clone(),
+                    credit: clip.user_info.display_name.to_string(),
+                    in_sec: format!("{:.2}s", trim.in_sec.unwrap_or(0.0)),
+                    out_sec: format!("{:.2}s", trim.out_sec.unwrap_or(duration_sec)),
+                    removed: format!("{:.2}s", duration_sec - trim.duration_sec(duration_sec)),
+                },
+            ));
+        }
+    }
+
+    pb.finish_and_clear();
+
+    if rows.is_empty() {
+        println!("nothing to trim");
+    } else {
+        rows.sort_by_key(|(index, _)| *index);
+
+        let table = Table::new(rows.into_iter().map(|(_, row)| row))
+            .with(Style::modern())
+            .clone();
+        println!("{table}");
+        println!(
+            "the trims are saved in each clip's clip.toml, check them before running burn-credits"
+        );
+    }
+
+    if reburn > 0 {
+        println!(
+            "removed {reburn} burned clip(s) made with the old trims, run burn-credits again to update them"
+        );
+    }
+
+    if skipped > 0 {
+        println!(
+            "{skipped} clip(s) skipped (not downloaded, or already trimmed, use --redetect to scan auto-trimmed clips again)"
+        );
+    }
+
+    Ok(())
+}
+
+/// saves a detected trim in clip.toml, returns whether a clip burned with the old one was removed
+fn save_trim(clip_dir: &Path, trim: Option<Trim>) -> Result<bool> {
+    // only touch clip.toml if there is something to change, most clips don't need a trim
+    let mut settings = ClipSettings::load(clip_dir)?;
+    if trim == settings.trim {
+        return Ok(false);
+    }
+
+    settings.trim = trim;
+    settings.save(clip_dir)?;
+
+    // a clip burned with the old trim would otherwise be kept as it is
+    ClipSettings::remove_burned(clip_dir)
+}
+
+/// in and out points that cut off leading/trailing black frames and silence
+///
+/// `None` if there is nothing worth trimming
+fn propose_trim(gaps: &Gaps, duration_sec: f64) -> Option<Trim> {
+    let is_leading = |span: &Span| span.start <= EDGE_TOLERANCE_SEC;
+    let is_trailing = |span: &Span| span.end >= duration_sec - EDGE_TOLERANCE_SEC;
+    let is_long = |span: &Span| span.end - span.start >= MIN_SILENCE_SEC;
+
+    // black frames go completely, silence keeps a little bit on the side of the content
+    let leading = gaps
+        .black
+        .iter()
+        .filter(|span| is_leading(span))
+        .map(|span| span.end)
+        .chain(
+            gaps.silence
+                .iter()
+                .filter(|span| is_leading(span) && is_long(span))
+                .map(|span| span.end - SILENCE_MARGIN_SEC),
+        )
+        .fold(0.0_f64, f64::max);
+
+    let trailing = gaps
+        .black
+        .iter()
+        .filter(|span| is_trailing(span))
+        .map(|span| span.start)
+        .chain(
+            gaps.silence
+                .iter()
+                .filter(|span| is_trailing(span) && is_long(span))
+                .map(|span| span.start + SILENCE_MARGIN_SEC),
+        )
+        .fold(duration_sec, f64::min);
+
+    let in_sec = (leading > EDGE_TOLERANCE_SEC).then_some(leading);
+    let out_sec = (trailing < duration_sec - EDGE_TOLERANCE_SEC).then_some(trailing);
+
+    if in_sec.is_none() && out_sec.is_none() {
+        return None;
+    }
+
+    // a clip that's black or silent the whole way through isn't something to fix by trimming
+    if trailing - leading < MIN_TRIMMED_LEN_SEC {
+        return None;
+    }
+
+    Some(Trim {
+        in_sec,
+        out_sec,
+        auto: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+
+    use super::propose_trim;
+    use crate::probe::{Gaps, Span};
+
+    const fn span(start: f64, end: f64) -> Span {
+        Span { start, end }
+    }
+
+    #[test]
+    fn nothing_to_trim() {
+        let gaps = Gaps {
+            black: vec![span(4.0, 4.5)],
+            silence: vec![span(0.0, 0.6)],
+        };
+
+        assert!(propose_trim(&gaps, 10.0).is_none());
+    }
+
+    #[test]
+    fn cuts_black_frames_completely() -> Result<()> {
+        let gaps = Gaps {
+            black: vec![span(0.0, 1.5), span(9.0, 10.0)],
+            silence: Vec::new(),
+        };
+
+        let trim = propose_trim(&gaps, 10.0).context("expected a trim")?;
+        assert_eq!(trim.in_sec, Some(1.5));
+        assert_eq!(trim.out_sec, Some(9.0));
+        assert!(trim.auto);
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_a_bit_of_silence_that_runs_to_the_end() -> Result<()> {
+        // silencedetect didn't print an end, so the span ends at the end of the file
+        let gaps = Gaps {
+            black: Vec::new(),
+            silence: vec![span(7.0, 10.0)],
+        };
+
+        let trim = propose_trim(&gaps, 10.0).context("expected a trim")?;
+        assert_eq!(trim.in_sec, None);
+        assert_eq!(trim.out_sec, Some(7.25));
+        Ok(())
+    }
+
+    #[test]
+    fn leaves_clips_that_are_gaps_the_whole_way_through() {
+        let gaps = Gaps {
+            black: vec![span(0.0, 10.0)],
+            silence: vec![span(0.0, 10.0)],
+        };
+
+        assert!(propose_trim(&gaps, 10.0).is_none());
+    }
+}
@@ -1,10 +1,113 @@
-use crate::burner::credits::burn_credits;
+use std::path::Path;
+
+use crate::api::client::ApiClient;
+use crate::burner::credits::{ProbedClips, burn_credits, default_jobs, is_complete_burn};
+use crate::commands::probe::probe_local_clips;
 use crate::config::Config;
+use crate::download::ensure_avatar;
+use crate::local_clips::{LocalClip, list_local_clips};
+use crate::media_cache::{MediaCache, MediaInfo};
 use anyhow::{Context, Result};
 
-pub fn burn_credits_cmd(config: &Config, video_id: String, crf: Option<i32>) -> Result<()> {
-    burn_credits(&config.fs.out_dir.join(video_id), &config.fs.font_file, crf)
-        .context("failed to burn credits text")?;
+pub async fn burn_credits_cmd(
+    config: &Config,
+    api_client: &ApiClient,
+    video_id: String,
+    profile: Option<&str>,
+    crf: Option<i32>,
+    jobs: Option<usize>,
+) -> Result<()> {
+    let profile = config.profile(profile)?;
+    let video_dir = config.fs.out_dir.join(video_id);
+
+    let clips = list_local_clips(&video_dir)?;
+
+    if config.credits.avatar {
+        fetch_missing_avatars(&api_client.client, &clips).await;
+    }
+    let mut cache = MediaCache::load(&video_dir)
+        .await
+        .context("failed to load media cache")?;
+
+    // the clips are probed (and cached) up front, so the encodes don't each run ffprobe. this is
+    // also the first pass of the loudness normalisation, so clips are only analysed once
+    if config.loudness.enabled {
+        println!("measuring loudness");
+    }
+    probe_local_clips(&video_dir, &clips, &mut cache, config.loudness.enabled).await?;
+
+    let probed = probed_clips(&video_dir, &clips, &mut cache).await?;
+
+    burn_credits(
+        &video_dir,
+        config,
+        &profile,
+        crf,
+        jobs.unwrap_or_else(default_jobs),
+        &probed,
+    )
+    .context("failed to burn credits text")?;
 
     Ok(())
 }
+
+/// downloads the avatars that weren't cached with the clips, which happens when avatars were
+/// turned off while downloading
+///
+/// the credit falls back to just the name without an avatar, so this isn't worth failing over
+async fn fetch_missing_avatars(client: &reqwest::Client, clips: &[LocalClip]) {
+    for clip in clips.iter().filter(|clip| clip.video.is_some()) {
+        if let Err(e) = ensure_avatar(client, &clip.dir, &clip.user_info).await {
+            eprintln!("warning: no avatar for {}: {e:#}", clip.clip_id);
+        }
+    }
+}
+
+/// the cached probes of the downloaded clips, and which burned clips are complete
+///
+/// burned files that changed since they were last checked are probed again
+async fn probed_clips(
+    video_dir: &Path,
+    clips: &[LocalClip],
+    cache: &mut MediaCache,
+) -> Result<ProbedClips> {
+    let mut probed = ProbedClips::default();
+    let mut changed = false;
+
+    for clip in clips {
+        if let Some(path) = &clip.video
+            && let Some(info) = cache.file_info(&clip.clip_id, video_dir, path).await
+        {
+            probed.inputs.insert(path.clone(), info.clone());
+        }
+
+        let Some(burned) = &clip.burned else {
+            continue;
+        };
+
+        let info = match cache.burned_info(&clip.clip_id, video_dir, burned).await {
+            Some(info) => Some(info.clone()),
+            // a file ffprobe can't read is most likely an encode that got interrupted
+            None => MediaInfo::probe(video_dir, burned)
+                .await
+                .ok()
+                .inspect(|info| {
+                    cache.set_burned_info(clip.clip_id.clone(), info.clone());
+                    changed = true;
+                }),
+        };
+
+        if info.as_ref().is_some_and(is_complete_burn) {
+            probed.complete_outputs.insert(burned.clone());
+        }
+    }
+
+    if changed {
+        cache
+            .save(video_dir)
+            .await
+            .context("failed to save media cache")?;
+    }
+
+    Ok(probed)
+}
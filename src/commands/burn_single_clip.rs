@@ -1,12 +1,16 @@
-use std::borrow::Cow;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use crate::api::client::ApiClient;
 use crate::burner::credits::{EncodeTask, run_ffmpeg};
+use crate::burner::utils::encode_progress_style;
+use crate::clip_settings::ClipSettings;
 use crate::config::Config;
-use crate::download::{UserInfo, download_file_into_temp_dir};
+use crate::download::{UserInfo, download_avatar, download_file_into_temp_dir};
+use crate::media_cache::MediaInfo;
 use anyhow::Result;
 use clap::Args;
+use indicatif::ProgressBar;
 use tempfile::tempdir;
 
 #[derive(Args, Debug, Clone)]
@@ -15,6 +19,9 @@ pub struct BurnSingleClipArgs {
     pub output: PathBuf,
     #[arg(long)]
     pub clip_id: String,
+    /// encoding profile to use (defaults to `default_profile` from the config)
+    #[arg(long)]
+    pub profile: Option<String>,
 }
 
 pub async fn burn_single_clip_cmd(
@@ -22,33 +29,54 @@ pub async fn burn_single_clip_cmd(
     args: &BurnSingleClipArgs,
     api_client: &ApiClient,
 ) -> Result<()> {
+    let profile = config.profile(args.profile.as_deref())?;
+
     println!("burning");
     let temp_dir = tempdir()?;
     let res = api_client.get_single_clip(&args.clip_id).await?;
 
-    let user_info = res.clip.overridden_profile_data.as_ref().map_or_else(
-        || UserInfo {
-            user_id: Cow::Owned(res.clip.creator.id.clone()),
-            display_name: Cow::Owned(res.clip.creator.name.clone()),
-            username: Cow::Owned(format!("@{}", res.clip.creator.username)),
-        },
-        |profile| UserInfo {
-            user_id: Cow::Owned(format!("profile_{}", profile.id)),
-            display_name: Cow::Owned(profile.line1.clone()),
-            username: Cow::Owned(profile.line2.clone()),
-        },
-    );
-
-    let video_path =
-        download_file_into_temp_dir(&res.clip.url, &temp_dir, &api_client.client).await?;
+    let user_info = UserInfo::from_clip(&res.clip).into_owned();
+
+    let video_path = download_file_into_temp_dir(&res.clip, &temp_dir, &api_client.client).await?;
+
+    let settings = ClipSettings::load_for_clip(config, &res.clip.video_id, &res.clip.id)?;
+
+    // the clip is only downloaded for this run, so there is nothing to cache the measurement in
+    let mut media = MediaInfo::probe(temp_dir.path(), &video_path).await?;
+    if config.loudness.enabled && media.has_audio() {
+        media.measure_loudness(&video_path, settings.trim).await?;
+    }
+
+    // the credit is drawn without the avatar if it can't be downloaded
+    let avatar = if config.credits.avatar {
+        download_avatar(&api_client.client, &res.clip, temp_dir.path())
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("warning: {e:#}, burning without it");
+                None
+            })
+    } else {
+        None
+    };
 
     let encode_task = EncodeTask {
         input: video_path,
         output: args.output.clone(),
         user_info,
+        framing: settings.framing(&config.framing),
+        media: Some(media),
+        trim: settings.trim,
+        avatar,
     };
 
-    run_ffmpeg(&encode_task, &config.fs.font_file, None)?;
+    let pb = ProgressBar::new(0);
+    pb.set_style(encode_progress_style()?);
+    pb.set_message("Encoding");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let result = run_ffmpeg(&encode_task, config, &profile, None, &pb);
+    pb.finish_and_clear();
+    result?;
 
     println!("done");
 
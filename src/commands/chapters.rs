@@ -0,0 +1,192 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::burner::utils::get_video_duration_sec;
+use crate::config::Config;
+use crate::download::UserInfo;
+use crate::local_clips::list_local_clips;
+
+/// youtube ignores chapters shorter than this
+const MIN_CHAPTER_LEN: Duration = Duration::from_secs(10);
+
+#[derive(Args, Debug, Clone)]
+pub struct ChaptersArgs {
+    /// the id of the video
+    pub video_id: String,
+
+    /// processed intro clip that comes before the first clip
+    #[arg(long)]
+    pub intro: Option<PathBuf>,
+
+    /// processed outro clip that comes after the last clip
+    #[arg(long)]
+    pub outro: Option<PathBuf>,
+
+    /// seconds each clip overlaps the one before it (for transitions)
+    #[arg(long, default_value_t = 0.0)]
+    pub overlap: f64,
+}
+
+pub struct Chapter {
+    pub start: Duration,
+    pub title: String,
+}
+
+/// the chapters of a video, and its total length
+pub struct ChapterList {
+    pub chapters: Vec<Chapter>,
+    pub total: Duration,
+}
+
+impl ChapterList {
+    /// builds the chapter list from the clips' running order and probed durations
+    ///
+    /// only burned clips are used, since those are what `video assemble` puts in the video
+    pub fn build(
+        video_dir: &Path,
+        intro: Option<&Path>,
+        outro: Option<&Path>,
+        overlap_sec: f64,
+    ) -> Result<Self> {
+        let mut segments = Vec::new();
+
+        if let Some(intro) = intro {
+            segments.push(("intro".to_string(), intro.to_path_buf()));
+        }
+
+        for clip in list_local_clips(video_dir)? {
+            let Some(file) = clip.burned else {
+                if clip.video.is_some() {
+                    eprintln!(
+                        "warning: {} hasn't been burned yet and will be left out (run burn-credits first)",
+                        clip.dir.display()
+                    );
+                }
+                continue;
+            };
+            segments.push((chapter_title(&clip.user_info), file));
+        }
+
+        if let Some(outro) = outro {
+            segments.push(("outro".to_string(), outro.to_path_buf()));
+        }
+
+        let mut chapters = Vec::with_capacity(segments.len());
+        let mut position_sec: f64 = 0.0;
+
+        for (index, (title, file)) in segments.into_iter().enumerate() {
+            let duration_sec = get_video_duration_sec(&file)
+                .with_context(|| format!("failed to get the duration of {}", file.display()))?;
+
+            // every clip after the first one starts while the previous one is still going
+            if index > 0 {
+                position_sec = (position_sec - overlap_sec).max(0.0);
+            }
+
+            chapters.push(Chapter {
+                start: Duration::from_secs_f64(position_sec),
+                title,
+            });
+
+            position_sec += duration_sec;
+        }
+
+        Ok(Self {
+            chapters,
+            total: Duration::from_secs_f64(position_sec),
+        })
+    }
+
+    /// the chapters in the format youtube picks up from a description
+    pub fn to_description_text(&self) -> String {
+        let with_hours = self.total.as_secs() >= 3600;
+
+        self.chapters
+            .iter()
+            .fold(String::new(), |mut out, chapter| {
+                let _ = writeln!(
+                    out,
+                    "{} {}",
+                    format_timestamp(chapter.start, with_hours),
+                    chapter.title
+                );
+                out
+            })
+    }
+
+    /// things that would make youtube ignore the chapters
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if self.chapters.len() < 3 {
+            warnings.push("youtube needs at least 3 chapters to show them".to_string());
+        }
+
+        let ends = self
+            .chapters
+            .iter()
+            .skip(1)
+            .map(|c| c.start)
+            .chain(std::iter::once(self.total));
+
+        for (chapter, end) in self.chapters.iter().zip(ends) {
+            if end.saturating_sub(chapter.start) < MIN_CHAPTER_LEN {
+                warnings.push(format!(
+                    "\"{}\" is shorter than {} seconds, youtube won't accept that",
+                    chapter.title,
+                    MIN_CHAPTER_LEN.as_secs()
+                ));
+            }
+        }
+
+        warnings
+    }
+}
+
+pub fn handle(config: &Config, args: &ChaptersArgs) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(&args.video_id);
+
+    let list = ChapterList::build(
+        &video_dir,
+        args.intro.as_deref(),
+        args.outro.as_deref(),
+        args.overlap,
+    )?;
+
+    if list.chapters.is_empty() {
+        anyhow::bail!("no clips found in {}", video_dir.display());
+    }
+
+    for warning in list.warnings() {
+        eprintln!("warning: {warning}");
+    }
+
+    print!("{}", list.to_description_text());
+
+    Ok(())
+}
+
+fn chapter_title(user_info: &UserInfo) -> String {
+    if user_info.username.trim().is_empty() {
+        user_info.display_name.to_string()
+    } else {
+        user_info.username.to_string()
+    }
+}
+
+fn format_timestamp(duration: Duration, with_hours: bool) -> String {
+    let total_secs = duration.as_secs();
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let seconds = total_secs % 60;
+
+    if with_hours {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use tabled::{Table, Tabled, settings::Style};
+
+use crate::commands::probe::probe_local_clips;
+use crate::config::{Config, EncodingProfile};
+use crate::local_clips::list_local_clips;
+use crate::media_cache::{MediaCache, MediaInfo};
+
+/// clips shorter than this are probably a mistake
+const MIN_DURATION_SEC: f64 = 3.0;
+/// clips longer than this take up a big part of the video
+const MAX_DURATION_SEC: f64 = 180.0;
+/// below this the clip will look blocky once it's scaled up
+const MIN_BIT_RATE: u64 = 500_000;
+/// above this the clip is probably a raw recording and will be slow to encode
+const MAX_BIT_RATE: u64 = 50_000_000;
+/// anything below this looks choppy next to the other clips
+const MIN_FPS: f64 = 24.0;
+/// sample rates that don't need resampling to sound right
+const COMMON_SAMPLE_RATES: [u32; 2] = [44100, 48000];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    /// will be handled when burning, just good to know
+    Info,
+    /// will burn, but probably won't look or sound right
+    Warning,
+    /// won't burn at all
+    Error,
+}
+
+impl Severity {
+    fn label(self) -> String {
+        match self {
+            Self::Info => "info".blue().to_string(),
+            Self::Warning => "warning".yellow().bold().to_string(),
+            Self::Error => "error".red().bold().to_string(),
+        }
+    }
+}
+
+struct Issue {
+    severity: Severity,
+    message: String,
+}
+
+impl Issue {
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct IssueRow {
+    clip: String,
+    credit: String,
+    severity: String,
+    problem: String,
+}
+
+pub async fn handle(
+    config: &Config,
+    video_id: &str,
+    strict: bool,
+    profile: Option<&str>,
+) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(video_id);
+
+    if !video_dir.is_dir() {
+        anyhow::bail!(
+            "nothing has been downloaded for this video yet, run `ttcore-clip-preparer video download {video_id}` first"
+        );
+    }
+
+    let profile = config.profile(profile)?;
+    let clips = list_local_clips(&video_dir)?;
+    let mut cache = MediaCache::load(&video_dir)
+        .await
+        .context("failed to load media cache")?;
+
+    let summary = probe_local_clips(&video_dir, &clips, &mut cache, false).await?;
+
+    let mut rows = Vec::new();
+    let mut counts = [0; 3];
+
+    for clip in &clips {
+        let mut issues = match (&clip.video, summary.failed.get(&clip.clip_id)) {
+            (None, _) => vec![Issue::new(Severity::Warning, "not downloaded")],
+            (Some(_), Some(e)) => vec![Issue::new(
+                Severity::Error,
+                format!("corrupt or unreadable file: {e}"),
+            )],
+            (Some(path), None) => cache
+                .last_file_info(&clip.clip_id)
+                .filter(|info| video_dir.join(&info.path) == *path)
+                .map_or_else(
+                    || vec![Issue::new(Severity::Error, "couldn't be probed")],
+                    |info| check_clip(info, &profile),
+                ),
+        };
+
+        issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+
+        for issue in issues {
+            counts[issue.severity as usize] += 1;
+
+            rows.push(IssueRow {
+                clip: clip.clip_id.clone(),
+                credit: clip.user_info.display_name.to_string(),
+                severity: issue.severity.label(),
+                problem: issue.message,
+            });
+        }
+    }
+
+    let [infos, warnings, errors] = counts;
+
+    if rows.is_empty() {
+        println!("{}", "every clip looks fine".green());
+    } else {
+        let table = Table::new(rows).with(Style::modern()).clone();
+        println!("{table}");
+    }
+
+    println!(
+        "checked {} clip(s): {errors} error(s), {warnings} warning(s), {infos} info",
+        clips.len()
+    );
+
+    if errors > 0 || (strict && warnings > 0) {
+        anyhow::bail!("some clips need fixing before burning");
+    }
+
+    Ok(())
+}
+
+/// everything about a probed clip that doesn't fit the output of `profile` well
+fn check_clip(info: &MediaInfo, profile: &EncodingProfile) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let (width, height, fps) = (profile.width, profile.height, profile.fps);
+
+    if info.duration_sec <= 0.0 {
+        issues.push(Issue::new(Severity::Error, "the clip has no length"));
+    } else if info.duration_sec < MIN_DURATION_SEC {
+        issues.push(Issue::new(
+            Severity::Warning,
+            format!("very short ({:.1}s)", info.duration_sec),
+        ));
+    } else if info.duration_sec > MAX_DURATION_SEC {
+        issues.push(Issue::new(
+            Severity::Warning,
+            format!("very long ({:.0}s)", info.duration_sec),
+        ));
+    }
+
+    if info.width == 0 || info.height == 0 {
+        issues.push(Issue::new(Severity::Error, "unknown resolution"));
+    } else {
+        if info.height > info.width {
+            issues.push(Issue::new(
+                Severity::Warning,
+                format!(
+                    "vertical video ({}x{}), it will have big bars on the sides",
+                    info.width, info.height
+                ),
+            ));
+        }
+
+        if info.width > width || info.height > height {
+            issues.push(Issue::new(
+                Severity::Info,
+                format!(
+                    "{}x{} will be scaled down to {width}x{height}",
+                    info.width, info.height
+                ),
+            ));
+        } else if info.width.max(info.height) < 1280 {
+            issues.push(Issue::new(
+                Severity::Warning,
+                format!(
+                    "low resolution ({}x{}), it will look blurry",
+                    info.width, info.height
+                ),
+            ));
+        }
+    }
+
+    if info.fps < MIN_FPS {
+        issues.push(Issue::new(
+            Severity::Warning,
+            format!("low frame rate ({:.2} fps)", info.fps),
+        ));
+    } else if info.fps > f64::from(fps) + 0.5 {
+        issues.push(Issue::new(
+            Severity::Info,
+            format!("{:.2} fps will be dropped to {fps} fps", info.fps),
+        ));
+    }
+
+    if info.is_variable_frame_rate() {
+        issues.push(Issue::new(
+            Severity::Warning,
+            format!(
+                "variable frame rate ({:.2} average, {:.2} base), audio may drift",
+                info.fps, info.base_fps
+            ),
+        ));
+    }
+
+    if let Some(bit_rate) = info.bit_rate {
+        if bit_rate < MIN_BIT_RATE {
+            issues.push(Issue::new(
+                Severity::Warning,
+                format!("very low bitrate ({} kb/s)", bit_rate / 1000),
+            ));
+        } else if bit_rate > MAX_BIT_RATE {
+            issues.push(Issue::new(
+                Severity::Warning,
+                format!("very high bitrate ({} Mb/s)", bit_rate / 1_000_000),
+            ));
+        }
+    }
+
+    if info.video_codec == "hevc" {
+        issues.push(Issue::new(
+            Severity::Info,
+            "hevc, which is slow to decode and some editors can't open",
+        ));
+    }
+
+    if info.has_audio() {
+        if let Some(rate) = info
+            .sample_rate
+            .filter(|rate| !COMMON_SAMPLE_RATES.contains(rate))
+        {
+            issues.push(Issue::new(
+                Severity::Info,
+                format!("unusual audio sample rate ({rate} Hz) will be resampled"),
+            ));
+        }
+    } else {
+        issues.push(Issue::new(Severity::Info, "no audio, silence is added"));
+    }
+
+    issues
+}
@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::api::client::ApiClient;
+use crate::burner::utils::get_video_duration_sec;
+use crate::clip_settings::{ClipSettings, Trim};
+use crate::config::Config;
+use crate::download::USER_INFO_FILE_NAME;
+use crate::local_clips::first_video_file;
+
+#[derive(Args, Debug, Clone)]
+pub struct ClipTrimArgs {
+    /// the id of the clip
+    pub clip_id: String,
+
+    /// where the clip should start (`12`, `00:12`, `1:02:03.5`, ...)
+    #[arg(long = "in", value_name = "TIME", value_parser = parse_timestamp)]
+    pub in_sec: Option<f64>,
+
+    /// where the clip should end
+    #[arg(long = "out", value_name = "TIME", value_parser = parse_timestamp)]
+    pub out_sec: Option<f64>,
+
+    /// remove the trim, so the whole clip is used again
+    #[arg(long, conflicts_with_all = ["in_sec", "out_sec"])]
+    pub clear: bool,
+}
+
+pub async fn handle(config: &Config, api_client: &ApiClient, args: &ClipTrimArgs) -> Result<()> {
+    let clip_dir = find_clip_dir(config, api_client, &args.clip_id).await?;
+    let mut settings = ClipSettings::load(&clip_dir)?;
+
+    if args.clear {
+        settings.trim = None;
+    } else if args.in_sec.is_none() && args.out_sec.is_none() {
+        match settings.trim {
+            Some(trim) => println!("{}", describe(&trim)),
+            None => println!("the clip isn't trimmed"),
+        }
+        return Ok(());
+    } else {
+        // only the given side changes, the other one stays where it was
+        let existing = settings.trim.unwrap_or_default();
+        let trim = Trim {
+            in_sec: args.in_sec.or(existing.in_sec),
+            out_sec: args.out_sec.or(existing.out_sec),
+            auto: false,
+        };
+
+        validate(&trim, &clip_dir)?;
+        println!("{}", describe(&trim));
+        settings.trim = Some(trim);
+    }
+
+    std::fs::create_dir_all(&clip_dir)
+        .with_context(|| format!("failed to create {}", clip_dir.display()))?;
+    settings.save(&clip_dir)?;
+
+    // the burned clip was made with the old trim, so it has to be burned again
+    if ClipSettings::remove_burned(&clip_dir)? {
+        println!("removed the old burned clip, run burn-credits again to update it");
+    }
+
+    Ok(())
+}
+
+/// the folder of a clip in `out/`, which is looked up on the frontend if it hasn't been
+/// downloaded yet (the trim is then used once it is)
+async fn find_clip_dir(config: &Config, api_client: &ApiClient, clip_id: &str) -> Result<PathBuf> {
+    if let Ok(videos) = std::fs::read_dir(&config.fs.out_dir) {
+        for video in videos.flatten() {
+            let clip_dir = video.path().join(clip_id);
+
+            if clip_dir.join(USER_INFO_FILE_NAME).is_file() {
+                return Ok(clip_dir);
+            }
+        }
+    }
+
+    let res = api_client
+        .get_single_clip(clip_id)
+        .await
+        .context("failed to fetch the clip")?;
+
+    Ok(config.fs.out_dir.join(&res.clip.video_id).join(clip_id))
+}
+
+fn validate(trim: &Trim, clip_dir: &Path) -> Result<()> {
+    if let (Some(in_sec), Some(out_sec)) = (trim.in_sec, trim.out_sec)
+        && in_sec >= out_sec
+    {
+        anyhow::bail!("the in point has to be before the out point");
+    }
+
+    let Some(video) = first_video_file(&clip_dir.join("video")) else {
+        return Ok(());
+    };
+    let duration_sec = get_video_duration_sec(&video)?;
+
+    if trim.in_sec.is_some_and(|in_sec| in_sec >= duration_sec) {
+        anyhow::bail!("the in point is past the end of the clip ({duration_sec:.2}s long)");
+    }
+    if trim.out_sec.is_some_and(|out_sec| out_sec > duration_sec) {
+        eprintln!(
+            "warning: the out point is past the end of the clip ({duration_sec:.2}s long), the clip will play until its end"
+        );
+    }
+
+    Ok(())
+}
+
+fn describe(trim: &Trim) -> String {
+    let side = |sec: Option<f64>, fallback: &str| {
+        sec.map_or_else(|| fallback.to_string(), |sec| format!("{sec:.2}s"))
+    };
+
+    format!(
+        "the clip is trimmed from {} to {}{}",
+        side(trim.in_sec, "the start"),
+        side(trim.out_sec, "the end"),
+        if trim.auto {
+            " (found by auto-trim)"
+        } else {
+            ""
+        }
+    )
+}
+
+/// parses `SS`, `MM:SS` or `HH:MM:SS`, where the seconds can have a fraction
+fn parse_timestamp(text: &str) -> Result<f64, String> {
+    let mut total = 0.0;
+
+    for part in text.trim().split(':') {
+        let value: f64 = part
+            .parse()
+            .map_err(|_| format!("`{text}` isn't a timestamp like `01:40` or `100.5`"))?;
+
+        // `inf` and `nan` parse as floats too
+        if !value.is_finite() {
+            return Err(format!(
+                "`{text}` isn't a timestamp like `01:40` or `100.5`"
+            ));
+        }
+        if value < 0.0 {
+            return Err("timestamps can't be negative".to_string());
+        }
+
+        total = total * 60.0 + value;
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_timestamp;
+
+    #[test]
+    fn parses_every_format() {
+        assert_eq!(parse_timestamp("100.5"), Ok(100.5));
+        assert_eq!(parse_timestamp("01:40"), Ok(100.0));
+        assert_eq!(parse_timestamp("1:02:03.5"), Ok(3723.5));
+        assert_eq!(parse_timestamp(" 0:05 "), Ok(5.0));
+    }
+
+    #[test]
+    fn rejects_negative_values() {
+        assert!(parse_timestamp("-5").is_err());
+        assert!(parse_timestamp("1:-30").is_err());
+    }
+
+    #[test]
+    fn rejects_things_that_arent_timestamps() {
+        assert!(parse_timestamp("").is_err());
+        assert!(parse_timestamp("1::30").is_err());
+        assert!(parse_timestamp("1m30s").is_err());
+        assert!(parse_timestamp("inf").is_err());
+        assert!(parse_timestamp("nan").is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::api::client::{ApiClient, CreateNewVideoRequest};
+
+pub async fn handle(api_client: &ApiClient, title: String) -> Result<()> {
+    let response = api_client
+        .create_video(&CreateNewVideoRequest { title })
+        .await
+        .context("failed to create a video")?;
+
+    if response.success {
+        if let Some(id) = response.video_id {
+            let manage_url = api_client
+                .base_url
+                .join(&format!("videos/{id}"))
+                .context("failed to join video id to base url (manage url)")?;
+
+            let submit_url = api_client
+                .base_url
+                .join(&format!("submit/{id}"))
+                .context("failed to join video id to base url (submit url)")?;
+
+            println!(
+                "\n  {}\n\n  {:<8} {}\n  {:<8} {}\n  {:<8} {}\n",
+                "successfully created new video".green().bold(),
+                "id:".bold(),
+                id.cyan(),
+                "manage:".bold(),
+                manage_url.as_str().blue().underline(),
+                "submit:".bold(),
+                submit_url.as_str().blue().underline()
+            );
+        } else {
+            // the server said success, but didn't provide an id (realistically this should never happen)
+            eprintln!("uh oh: server reported success but returned no video id");
+        }
+    } else {
+        println!("failed to create video");
+    }
+
+    Ok(())
+}
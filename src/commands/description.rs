@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::api::client::ApiClient;
+use crate::commands::chapters::ChapterList;
+use crate::commands::total_length::format_duration;
+use crate::config::Config;
+use crate::download::UserInfo;
+use crate::order::ClipOrder;
+use crate::template;
+
+const DEFAULT_TEMPLATE: &str = "\
+{{ title }}
+
+thank you to everyone who submitted a clip! ({{ clip_count }} clips, {{ total_length }})
+
+{{ credits }}
+
+{{ chapters }}";
+
+#[derive(Args, Debug, Clone)]
+pub struct DescriptionArgs {
+    /// the id of the video
+    pub video_id: String,
+
+    /// template to render, overrides `fs.description_template` from the config
+    ///
+    /// available placeholders: `{{ title }}`, `{{ video_id }}`, `{{ chapters }}`,
+    /// `{{ credits }}`, `{{ usernames }}`, `{{ clip_count }}` and `{{ total_length }}`
+    #[arg(short, long)]
+    pub template: Option<PathBuf>,
+
+    /// processed intro clip, used for the chapters
+    #[arg(long)]
+    pub intro: Option<PathBuf>,
+
+    /// processed outro clip, used for the chapters
+    #[arg(long)]
+    pub outro: Option<PathBuf>,
+
+    /// seconds each clip overlaps the one before it (for transitions)
+    #[arg(long, default_value_t = 0.0)]
+    pub overlap: f64,
+
+    /// write the description into a file instead of printing it
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+pub async fn handle(config: &Config, api_client: &ApiClient, args: &DescriptionArgs) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(&args.video_id);
+
+    let template_path = args
+        .template
+        .as_ref()
+        .or(config.fs.description_template.as_ref());
+    let template_text = match template_path {
+        Some(path) => tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read template {}", path.display()))?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+
+    let mut clips = api_client
+        .list_selected_clips_for_video(&args.video_id, true)
+        .await
+        .context("failed to fetch clips for video")?
+        .clips;
+
+    clips.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    if let Some(order) = ClipOrder::load(&video_dir)? {
+        order.sort(&mut clips, |clip| Some(clip.id.as_str()));
+    }
+
+    let title = api_client
+        .list_videos()
+        .await
+        .context("failed to fetch videos")?
+        .videos
+        .into_iter()
+        .find(|video| video.id == args.video_id)
+        .map_or_else(|| args.video_id.clone(), |video| video.title);
+
+    // someone with several clips only gets credited once
+    let mut seen = HashSet::new();
+    let credited: Vec<UserInfo> = clips
+        .iter()
+        .map(UserInfo::from_clip)
+        .filter(|user_info| seen.insert(user_info.user_id.clone()))
+        .collect();
+
+    let credits = credited
+        .iter()
+        .map(|user_info| format!("{} ({})", user_info.display_name, user_info.username))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let usernames = credited
+        .iter()
+        .map(|user_info| user_info.username.as_ref())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // chapters and the length need the clips on disk, the rest of the description doesn't
+    let chapter_list = if tokio::fs::try_exists(&video_dir).await? {
+        match ChapterList::build(
+            &video_dir,
+            args.intro.as_deref(),
+            args.outro.as_deref(),
+            args.overlap,
+        ) {
+            Ok(list) => Some(list),
+            Err(e) => {
+                eprintln!("warning: couldn't work out the chapters: {e:#}");
+                None
+            }
+        }
+    } else {
+        eprintln!("warning: no clips downloaded yet, leaving out the chapters and length");
+        None
+    };
+
+    let (chapters, total_length) = chapter_list.map_or_else(
+        || (String::new(), "unknown length".to_string()),
+        |list| {
+            (
+                list.to_description_text().trim_end().to_string(),
+                format_duration(list.total),
+            )
+        },
+    );
+
+    let values = HashMap::from([
+        ("title", title),
+        ("video_id", args.video_id.clone()),
+        ("chapters", chapters),
+        ("credits", credits),
+        ("usernames", usernames),
+        ("clip_count", clips.len().to_string()),
+        ("total_length", total_length),
+    ]);
+
+    let description = template::render(&template_text, &values)?;
+
+    match &args.output {
+        Some(path) => {
+            tokio::fs::write(path, &description)
+                .await
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!("description saved to {}", path.display());
+        }
+        None => println!("{description}"),
+    }
+
+    Ok(())
+}
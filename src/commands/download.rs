@@ -8,9 +8,14 @@ pub async fn download_command(
     config: &Config,
     api_client: &ApiClient,
 ) -> Result<()> {
-    download::download_selected_files(&video_id, config, api_client)
+    let summary = download::download_selected_files(&video_id, config, api_client)
         .await
         .context("download command failed")?;
 
+    println!(
+        "downloaded {} clip(s), skipped {} already up to date, {} failed",
+        summary.downloaded, summary.skipped, summary.failed
+    );
+
     Ok(())
 }
@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+
+use crate::config::Config;
+use crate::local_clips::list_local_clips;
+use crate::timeline::{Timeline, TimelineClip, edl, fcpxml, otio};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TimelineFormat {
+    /// final cut pro xml (also imported by resolve)
+    Fcpxml,
+    /// opentimelineio
+    Otio,
+    /// cmx 3600 edit decision list
+    Edl,
+}
+
+impl TimelineFormat {
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Fcpxml => "fcpxml",
+            Self::Otio => "otio",
+            Self::Edl => "edl",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum TimelineSource {
+    /// the clips with the credits burned in (falls back to the downloaded clip if not burned yet)
+    #[default]
+    Burned,
+    /// the clips as they were downloaded, so the credits can be added in the editor
+    Downloaded,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ExportTimelineArgs {
+    /// the id of the video
+    pub video_id: String,
+
+    #[arg(long, value_enum)]
+    pub format: TimelineFormat,
+
+    /// which version of the clips the timeline points at
+    #[arg(long, value_enum, default_value_t)]
+    pub source: TimelineSource,
+
+    /// processed intro clip to put before the first clip
+    #[arg(long)]
+    pub intro: Option<PathBuf>,
+
+    /// processed outro clip to put after the last clip
+    #[arg(long)]
+    pub outro: Option<PathBuf>,
+
+    /// where to save the timeline (defaults to `out/<video id>/timeline.<format>`)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// encoding profile the clips were burned with, for the timeline's resolution and frame rate
+    #[arg(long)]
+    pub profile: Option<String>,
+}
+
+pub fn handle(config: &Config, args: &ExportTimelineArgs) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(&args.video_id);
+    let profile = config.profile(args.profile.as_deref())?;
+    let mut clips = Vec::new();
+
+    if let Some(intro) = &args.intro {
+        clips.push(TimelineClip::probe(
+            "intro".to_string(),
+            intro,
+            profile.fps,
+            None,
+        )?);
+    }
+
+    for clip in list_local_clips(&video_dir)? {
+        let file = match args.source {
+            TimelineSource::Burned => {
+                if clip.burned.is_none() {
+                    eprintln!(
+                        "warning: {} hasn't been burned yet, using the downloaded clip",
+                        clip.dir.display()
+                    );
+                }
+                clip.best_file()
+            }
+            TimelineSource::Downloaded => clip.video.as_deref(),
+        };
+
+        let Some(file) = file else {
+            continue;
+        };
+
+        let name = clip
+            .user_info
+            .title
+            .as_deref()
+            .unwrap_or(&clip.clip_id)
+            .to_string();
+        let credit = format!(
+            "{} ({})",
+            clip.user_info.display_name, clip.user_info.username
+        );
+
+        clips.push(TimelineClip::probe(name, file, profile.fps, Some(credit))?);
+    }
+
+    if let Some(outro) = &args.outro {
+        clips.push(TimelineClip::probe(
+            "outro".to_string(),
+            outro,
+            profile.fps,
+            None,
+        )?);
+    }
+
+    if clips.is_empty() {
+        anyhow::bail!("no clips found in {}", video_dir.display());
+    }
+
+    let timeline = Timeline {
+        name: args.video_id.clone(),
+        fps: profile.fps,
+        width: profile.width,
+        height: profile.height,
+        clips,
+    };
+
+    let text = match args.format {
+        TimelineFormat::Fcpxml => fcpxml::render(&timeline)?,
+        TimelineFormat::Otio => otio::render(&timeline)?,
+        TimelineFormat::Edl => edl::render(&timeline),
+    };
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| video_dir.join(format!("timeline.{}", args.format.extension())));
+
+    std::fs::write(&output, text)
+        .with_context(|| format!("failed to write {}", output.display()))?;
+
+    println!("✅ timeline saved as {}", output.display());
+
+    Ok(())
+}
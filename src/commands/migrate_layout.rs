@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use convert_case::{Case, Casing};
+
+use crate::{
+    api::{client::ApiClient, clips::Clip},
+    config::Config,
+    download::{USER_INFO_FILE_NAME, clip_file_name, clip_relative_path, write_user_info},
+    fs::move_file,
+    manifest::{Manifest, ManifestEntry},
+};
+
+/// moves clips downloaded with the old `<author>/video/<file name from the url>` layout into
+/// the `<clip id>/video/<clip id>.<ext>` one
+pub async fn handle(config: &Config, api_client: &ApiClient, video_id: &str) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(video_id);
+
+    if !tokio::fs::try_exists(&video_dir).await? {
+        println!("nothing downloaded for this video, nothing to migrate");
+        return Ok(());
+    }
+
+    // deselected clips might still be on disk too, so look at all of them
+    let clips = api_client
+        .list_selected_clips_for_video(video_id, false)
+        .await
+        .context("failed to fetch clips for video")?
+        .clips;
+
+    let mut manifest = Manifest::load(&video_dir).await?;
+
+    // several clips could have been written to the same old path, so find out who claims what
+    // before moving anything
+    let mut claims: BTreeMap<PathBuf, Vec<&Clip>> = BTreeMap::new();
+
+    for clip in &clips {
+        let old_path = manifest
+            .clips
+            .get(&clip.id)
+            .map_or_else(|| legacy_clip_path(clip), |entry| entry.path.clone());
+
+        if old_path == clip_relative_path(clip)
+            || !tokio::fs::try_exists(video_dir.join(&old_path)).await?
+        {
+            continue;
+        }
+
+        claims.entry(old_path).or_default().push(clip);
+    }
+
+    let mut migrated = Vec::new();
+    let mut ambiguous = Vec::new();
+    let mut old_dirs = BTreeSet::new();
+
+    for (old_path, owners) in claims {
+        if let Some(dir) = old_path.parent().and_then(Path::parent) {
+            old_dirs.insert(dir.to_path_buf());
+        }
+
+        let [clip] = owners.as_slice() else {
+            // there's no telling which of them the file on disk belongs to, forget about all of
+            // them so they get downloaded again
+            for clip in owners {
+                manifest.clips.remove(&clip.id);
+            }
+            ambiguous.push(old_path);
+            continue;
+        };
+
+        migrate_clip(&video_dir, &old_path, clip).await?;
+
+        // trees from before the manifest have no entry yet, without one the next download
+        // would fetch the clip again over the file that was just moved
+        if let Some(entry) = manifest.clips.get_mut(&clip.id) {
+            entry.path = clip_relative_path(clip);
+        } else {
+            let entry =
+                ManifestEntry::new(clip.url.clone(), &video_dir, clip_relative_path(clip)).await?;
+            manifest.clips.insert(clip.id.clone(), entry);
+        }
+
+        migrated.push(clip.title.clone());
+    }
+
+    manifest
+        .save(&video_dir)
+        .await
+        .context("failed to save download manifest")?;
+
+    for dir in &old_dirs {
+        remove_emptied_legacy_dir(&video_dir.join(dir)).await?;
+    }
+
+    println!("\n  {}\n", "migration complete".green().bold());
+    for title in &migrated {
+        println!("  {} {title}", "~".green().bold());
+    }
+    for path in &ambiguous {
+        println!(
+            "  {} {} (shared by several clips, left in place)",
+            "?".yellow().bold(),
+            path.display()
+        );
+    }
+
+    println!(
+        "\n  {} migrated, {} ambiguous\n",
+        migrated.len(),
+        ambiguous.len()
+    );
+
+    if !ambiguous.is_empty() {
+        println!(
+            "  run `video sync {video_id}` to re-download the ambiguous clips and move the leftover files out of the way\n"
+        );
+    }
+
+    Ok(())
+}
+
+/// where a clip would have been downloaded to before clips were stored by id
+fn legacy_clip_path(clip: &Clip) -> PathBuf {
+    let author_name = clip.overridden_profile_data.as_ref().map_or_else(
+        || clip.creator.username.clone(),
+        |profile| format!("profile__{}", profile.line1),
+    );
+
+    let filename = clip
+        .url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("video.mp4");
+
+    Path::new(&author_name.to_case(Case::Snake))
+        .join("video")
+        .join(filename)
+}
+
+async fn migrate_clip(video_dir: &Path, old_path: &Path, clip: &Clip) -> Result<()> {
+    let new_path = clip_relative_path(clip);
+    let clip_dir = video_dir.join(&clip.id);
+
+    move_file(&video_dir.join(old_path), &video_dir.join(&new_path)).await?;
+
+    if let Some(old_dir) = old_path.parent().and_then(Path::parent)
+        && let Some(file_name) = old_path.file_name()
+    {
+        let old_burned = video_dir.join(old_dir).join("burned").join(file_name);
+
+        if tokio::fs::try_exists(&old_burned).await? {
+            let new_burned = clip_dir.join("burned").join(clip_file_name(clip));
+            move_file(&old_burned, &new_burned).await?;
+        }
+    }
+
+    write_user_info(&clip_dir, clip)
+        .await
+        .with_context(|| format!("failed to write user info for {}", clip.title))
+}
+
+/// removes an old `<author>` folder once every clip in it has been moved out
+async fn remove_emptied_legacy_dir(dir: &Path) -> Result<()> {
+    let video_dir = dir.join("video");
+
+    if let Ok(mut entries) = tokio::fs::read_dir(&video_dir).await
+        && entries.next_entry().await?.is_some()
+    {
+        return Ok(());
+    }
+
+    let info_path = dir.join(USER_INFO_FILE_NAME);
+    if tokio::fs::try_exists(&info_path).await? {
+        tokio::fs::remove_file(&info_path)
+            .await
+            .with_context(|| format!("failed to remove {}", info_path.display()))?;
+    }
+
+    // only empty folders get removed, anything unexpected stays for the user to look at
+    for sub_dir in ["video", "burned"] {
+        let _ = tokio::fs::remove_dir(dir.join(sub_dir)).await;
+    }
+    let _ = tokio::fs::remove_dir(dir).await;
+
+    Ok(())
+}
@@ -1,18 +1,29 @@
+pub mod assemble;
+pub mod auto_trim;
 pub mod burn_credits;
 pub mod burn_single_clip;
+pub mod chapters;
+pub mod check;
 pub mod clip_count;
+pub mod clip_trim;
+pub mod create_video;
+pub mod description;
 pub mod download;
+pub mod export_timeline;
 pub mod list_videos;
+pub mod migrate_layout;
+pub mod order;
+pub mod probe;
+pub mod sync;
 pub mod total_length;
 pub mod update;
 
 use anyhow::{Context, Result};
-use colored::Colorize;
 use std::path::PathBuf;
 
 use crate::{
-    api::client::{ApiClient, CreateNewVideoRequest},
-    cli::{Commands, VideoCommands},
+    api::client::ApiClient,
+    cli::{ClipCommands, Commands, VideoCommands},
     config::Config,
     fs::{clean_burned_dirs, clean_output_dir, ensure_out_dir_exists},
 };
@@ -50,58 +61,13 @@ pub async fn execute(command: Commands, config_path: Option<PathBuf>) -> Result<
             crate::burner::outro_text::process_outro_text(&args, &config, &api_client).await?;
         }
 
-        Commands::Video(video_args) => match video_args.command {
-            VideoCommands::List => list_videos::handle(&api_client).await?,
-
-            VideoCommands::Create { title } => {
-                let response = api_client
-                    .create_video(&CreateNewVideoRequest { title })
-                    .await
-                    .context("failed to create a video")?;
-
-                if response.success {
-                    if let Some(id) = response.video_id {
-                        let manage_url = api_client
-                            .base_url
-                            .join(&format!("videos/{id}"))
-                            .context("failed to join video id to base url (manage url)")?;
-
-                        let submit_url = api_client
-                            .base_url
-                            .join(&format!("submit/{id}"))
-                            .context("failed to join video id to base url (submit url)")?;
-
-                        println!(
-                            "\n  {}\n\n  {:<8} {}\n  {:<8} {}\n  {:<8} {}\n",
-                            "successfully created new video".green().bold(),
-                            "id:".bold(),
-                            id.cyan(),
-                            "manage:".bold(),
-                            manage_url.as_str().blue().underline(),
-                            "submit:".bold(),
-                            submit_url.as_str().blue().underline()
-                        );
-                    } else {
-                        // the server said success, but didn't provide an id (realistically this should never happen)
-                        eprintln!("uh oh: server reported success but returned no video id");
-                    }
-                } else {
-                    println!("failed to create video");
-                }
-            }
-            VideoCommands::ClipCount { video_id } => {
-                clip_count::handle(&api_client, &video_id).await?;
-            }
-
-            VideoCommands::TotalLength { video_id } => {
-                total_length::handle(&api_client, &video_id).await?;
-            }
+        Commands::Video(video_args) => {
+            execute_video(video_args.command, &config, &api_client).await?;
+        }
 
-            VideoCommands::Download { video_id } => {
-                download::download_command(video_id, &config, &api_client).await?;
-            }
-            VideoCommands::BurnCredits { video_id, crf } => {
-                burn_credits::burn_credits_cmd(&config, video_id, crf)?;
+        Commands::Clip(clip_args) => match clip_args.command {
+            ClipCommands::Trim(args) => {
+                clip_trim::handle(&config, &api_client, &args).await?;
             }
         },
 
@@ -120,3 +86,91 @@ pub async fn execute(command: Commands, config_path: Option<PathBuf>) -> Result<
 
     Ok(())
 }
+
+async fn execute_video(
+    command: VideoCommands,
+    config: &Config,
+    api_client: &ApiClient,
+) -> Result<()> {
+    match command {
+        VideoCommands::List => list_videos::handle(api_client).await?,
+
+        VideoCommands::Create { title } => {
+            create_video::handle(api_client, title).await?;
+        }
+        VideoCommands::ClipCount { video_id } => {
+            clip_count::handle(api_client, &video_id).await?;
+        }
+
+        VideoCommands::TotalLength { video_id } => {
+            total_length::handle(config, api_client, &video_id).await?;
+        }
+
+        VideoCommands::Probe {
+            video_id,
+            skip_loudness,
+            profile,
+        } => {
+            probe::handle(config, &video_id, skip_loudness, profile.as_deref()).await?;
+        }
+
+        VideoCommands::Check {
+            video_id,
+            strict,
+            profile,
+        } => {
+            check::handle(config, &video_id, strict, profile.as_deref()).await?;
+        }
+
+        VideoCommands::AutoTrim { video_id, redetect } => {
+            auto_trim::handle(config, &video_id, redetect).await?;
+        }
+
+        VideoCommands::Download { video_id } => {
+            download::download_command(video_id, config, api_client).await?;
+        }
+        VideoCommands::Sync { video_id } => {
+            sync::handle(config, api_client, &video_id).await?;
+        }
+        VideoCommands::MigrateLayout { video_id } => {
+            migrate_layout::handle(config, api_client, &video_id).await?;
+        }
+        VideoCommands::Order {
+            video_id,
+            by,
+            reset,
+        } => {
+            order::handle(config, api_client, &video_id, by, reset).await?;
+        }
+        VideoCommands::Chapters(args) => {
+            chapters::handle(config, &args)?;
+        }
+        VideoCommands::Description(args) => {
+            description::handle(config, api_client, &args).await?;
+        }
+        VideoCommands::ExportTimeline(args) => {
+            export_timeline::handle(config, &args)?;
+        }
+        VideoCommands::Assemble(args) => {
+            assemble::assemble_cmd(config, &args)?;
+        }
+        VideoCommands::BurnCredits {
+            video_id,
+            profile,
+            crf,
+            jobs,
+        } => {
+            burn_credits::burn_credits_cmd(
+                config,
+                api_client,
+                video_id,
+                profile.as_deref(),
+                crf,
+                jobs,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::{
+    api::client::ApiClient,
+    config::Config,
+    order::{ClipOrder, OrderBy},
+};
+
+/// creates (or updates) the running order of a video from the clips selected on the frontend
+pub async fn handle(
+    config: &Config,
+    api_client: &ApiClient,
+    video_id: &str,
+    by: OrderBy,
+    reset: bool,
+) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(video_id);
+
+    let clips = api_client
+        .list_selected_clips_for_video(video_id, true)
+        .await
+        .context("failed to fetch clips for video")?
+        .clips;
+
+    let order = match ClipOrder::load(&video_dir)? {
+        Some(mut order) if !reset => {
+            order.update(&clips, by);
+            order
+        }
+        _ => ClipOrder::generate(&clips, by),
+    };
+
+    order.save(&video_dir)?;
+
+    println!();
+    for (index, entry) in order.clips.iter().enumerate() {
+        println!(
+            "  {:>3}. {} {}",
+            index + 1,
+            entry.title.bold(),
+            entry.credit.dimmed()
+        );
+    }
+    println!(
+        "\n  saved to {}, edit it to change the order\n",
+        ClipOrder::path(&video_dir).display().to_string().cyan()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::clip_settings::ClipSettings;
+use crate::config::{Config, EncodingProfile};
+use crate::local_clips::{LocalClip, list_local_clips};
+use crate::media_cache::{MediaCache, MediaInfo};
+
+#[derive(Tabled)]
+struct ProbeRow {
+    clip: String,
+    credit: String,
+    duration: String,
+    resolution: String,
+    fps: String,
+    video: String,
+    audio: String,
+    loudness: String,
+    sha256: String,
+}
+
+impl ProbeRow {
+    /// a row for a clip we couldn't probe, every property is left blank
+    fn missing(clip: String, credit: String, reason: &str) -> Self {
+        Self {
+            clip,
+            credit,
+            duration: reason.red().to_string(),
+            resolution: "-".into(),
+            fps: "-".into(),
+            video: "-".into(),
+            audio: "-".into(),
+            loudness: "-".into(),
+            sha256: "-".into(),
+        }
+    }
+
+    /// a row for a probed clip, with anything that doesn't match the output of `profile` highlighted
+    fn probed(clip: String, credit: String, info: &MediaInfo, profile: &EncodingProfile) -> Self {
+        let resolution = format!("{}x{}", info.width, info.height);
+        let resolution = if info.width == profile.width && info.height == profile.height {
+            resolution
+        } else {
+            resolution.yellow().to_string()
+        };
+
+        let fps = format!("{:.2}", info.fps);
+        let fps = if (info.fps - f64::from(profile.fps)).abs() < 0.01 {
+            fps
+        } else {
+            fps.yellow().to_string()
+        };
+
+        let audio = info
+            .audio_codec
+            .clone()
+            .unwrap_or_else(|| "none".red().to_string());
+
+        let loudness = info.loudness.map_or_else(
+            || "-".into(),
+            |loudness| format!("{:.1} LUFS", loudness.integrated),
+        );
+
+        Self {
+            clip,
+            credit,
+            duration: format!("{:.2}s", info.duration_sec),
+            resolution,
+            fps,
+            video: info.video_codec.clone(),
+            audio,
+            loudness,
+            sha256: info.sha256.chars().take(12).collect(),
+        }
+    }
+}
+
+/// what [`probe_local_clips`] did
+pub struct ProbeSummary {
+    pub probed: usize,
+    pub from_cache: usize,
+    /// clips whose file couldn't be probed, with the error
+    pub failed: BTreeMap<String, String>,
+}
+
+pub async fn handle(
+    config: &Config,
+    video_id: &str,
+    skip_loudness: bool,
+    profile: Option<&str>,
+) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(video_id);
+
+    if !video_dir.is_dir() {
+        anyhow::bail!(
+            "nothing has been downloaded for this video yet, run `ttcore-clip-preparer video download {video_id}` first"
+        );
+    }
+
+    let profile = config.profile(profile)?;
+    let clips = list_local_clips(&video_dir)?;
+    let mut cache = MediaCache::load(&video_dir)
+        .await
+        .context("failed to load media cache")?;
+
+    let summary = probe_local_clips(&video_dir, &clips, &mut cache, !skip_loudness).await?;
+
+    print_table(&video_dir, &clips, &cache, &profile);
+    println!(
+        "probed {} clip(s), {} were already in the cache",
+        summary.probed, summary.from_cache
+    );
+
+    Ok(())
+}
+
+/// probes the downloaded file of every clip that isn't in the cache yet, and saves the cache
+pub async fn probe_local_clips(
+    video_dir: &Path,
+    clips: &[LocalClip],
+    cache: &mut MediaCache,
+    measure_loudness: bool,
+) -> Result<ProbeSummary> {
+    let semaphore = Arc::new(Semaphore::new(4));
+
+    let pb = ProgressBar::new(clips.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(
+                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
+            )?
+            .progress_chars("#>-"),
+    );
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let mut set = JoinSet::new();
+    let mut summary = ProbeSummary {
+        probed: 0,
+        from_cache: 0,
+        failed: BTreeMap::new(),
+    };
+
+    for clip in clips {
+        let Some(path) = clip.video.clone() else {
+            pb.inc(1);
+            continue;
+        };
+
+        let cached = cache
+            .file_info(&clip.clip_id, video_dir, &path)
+            .await
+            .cloned();
+        // the loudness is measured again when the trim changes
+        let trim = ClipSettings::load(&clip.dir)?.trim;
+        let needs_loudness = move |info: &MediaInfo| {
+            measure_loudness && info.has_audio() && info.loudness_for(trim).is_none()
+        };
+
+        if cached.as_ref().is_some_and(|info| !needs_loudness(info)) {
+            summary.from_cache += 1;
+            pb.inc(1);
+            continue;
+        }
+
+        let clip_id = clip.clip_id.clone();
+        let video_dir = video_dir.to_path_buf();
+        let sem = Arc::clone(&semaphore);
+        let pb_task = pb.clone();
+
+        let permit = sem.acquire_owned().await.context("semaphore closed")?;
+
+        set.spawn(async move {
+            let _permit = permit;
+
+            let result = async {
+                let mut info = match cached {
+                    Some(info) => info,
+                    None => MediaInfo::probe(&video_dir, &path).await?,
+                };
+
+                if needs_loudness(&info) {
+                    info.measure_loudness(&path, trim).await?;
+                }
+
+                Ok::<_, anyhow::Error>(info)
+            }
+            .await;
+
+            pb_task.inc(1);
+
+            (clip_id, result)
+        });
+    }
+
+    while let Some(task_result) = set.join_next().await {
+        let (clip_id, result) = task_result.context("task panicked")?;
+
+        match result {
+            Ok(info) => {
+                cache.set_file_info(clip_id, info);
+                summary.probed += 1;
+            }
+            Err(e) => {
+                pb.println(format!("failed to probe clip {clip_id}: {e:#}"));
+                summary.failed.insert(clip_id, format!("{e:#}"));
+            }
+        }
+    }
+
+    pb.finish_and_clear();
+
+    if summary.probed > 0 {
+        cache
+            .save(video_dir)
+            .await
+            .context("failed to save media cache")?;
+    }
+
+    Ok(summary)
+}
+
+fn print_table(
+    video_dir: &Path,
+    clips: &[LocalClip],
+    cache: &MediaCache,
+    profile: &EncodingProfile,
+) {
+    let rows: Vec<ProbeRow> = clips
+        .iter()
+        .map(|clip| {
+            let credit = clip.user_info.display_name.to_string();
+
+            let Some(path) = clip.video.as_deref() else {
+                return ProbeRow::missing(clip.clip_id.clone(), credit, "not downloaded");
+            };
+
+            cache
+                .last_file_info(&clip.clip_id)
+                .filter(|info| video_dir.join(&info.path) == path)
+                .map_or_else(
+                    || ProbeRow::missing(clip.clip_id.clone(), credit.clone(), "probe failed"),
+                    |info| ProbeRow::probed(clip.clip_id.clone(), credit.clone(), info, profile),
+                )
+        })
+        .collect();
+
+    let table = Table::new(rows)
+        .with(Style::modern())
+        .with(Modify::new(Columns::new(2..5)).with(Alignment::right()))
+        .with(Modify::new(Columns::one(7)).with(Alignment::right()))
+        .clone();
+
+    println!("{table}");
+}
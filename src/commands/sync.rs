@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::{
+    api::client::ApiClient,
+    clip_settings::CLIP_SETTINGS_FILE_NAME,
+    config::Config,
+    download::{self, USER_INFO_FILE_NAME, is_partial_download},
+    fs::move_file,
+    manifest::Manifest,
+};
+
+/// where deselected clips are moved to, relative to the video directory
+pub const REMOVED_DIR_NAME: &str = "removed";
+
+/// makes `out/<video>` match the clips currently selected on the frontend
+///
+/// newly selected clips are downloaded, and deselected ones (along with anything burned from
+/// them) are moved into `out/<video>/removed` instead of being deleted
+pub async fn handle(config: &Config, api_client: &ApiClient, video_id: &str) -> Result<()> {
+    let video_dir = config.fs.out_dir.join(video_id);
+
+    let selected_clips = api_client
+        .list_selected_clips_for_video(video_id, true)
+        .await
+        .context("failed to fetch clips for video")?
+        .clips;
+
+    let selected_ids: HashSet<String> = selected_clips.iter().map(|c| c.id.clone()).collect();
+
+    let manifest_before = Manifest::load(&video_dir).await?;
+    let added: Vec<String> = selected_clips
+        .iter()
+        .filter(|c| !manifest_before.clips.contains_key(&c.id))
+        .map(|c| c.title.clone())
+        .collect();
+
+    let summary = download::download_clips(video_id, selected_clips, config, api_client)
+        .await
+        .context("failed to download selected clips")?;
+
+    let mut manifest = Manifest::load(&video_dir).await?;
+    let deselected: Vec<String> = manifest
+        .clips
+        .keys()
+        .filter(|id| !selected_ids.contains(*id))
+        .cloned()
+        .collect();
+
+    let mut removed = Vec::new();
+
+    for clip_id in deselected {
+        if let Some(entry) = manifest.clips.remove(&clip_id) {
+            move_clip_to_removed(&video_dir, &entry.path).await?;
+            removed.push(entry.path);
+        }
+    }
+
+    manifest
+        .save(&video_dir)
+        .await
+        .context("failed to save download manifest")?;
+
+    // clips downloaded before the manifest existed aren't in it, so anything that still isn't
+    // referenced now is no longer selected. this is only safe if every download went through
+    if summary.failed == 0 {
+        let tracked: HashSet<&Path> = manifest.clips.values().map(|e| e.path.as_path()).collect();
+
+        for path in untracked_clip_files(&video_dir, &tracked).await? {
+            move_clip_to_removed(&video_dir, &path).await?;
+            removed.push(path);
+        }
+    } else {
+        eprintln!(
+            "{} downloads failed, not checking for untracked clips this time",
+            summary.failed
+        );
+    }
+
+    print_report(&added, &removed, summary.skipped, summary.failed);
+
+    Ok(())
+}
+
+/// moves a downloaded clip (relative to `video_dir`) and its burned output into the removed area
+async fn move_clip_to_removed(video_dir: &Path, clip_path: &Path) -> Result<()> {
+    let removed_dir = video_dir.join(REMOVED_DIR_NAME);
+
+    let src = video_dir.join(clip_path);
+    if tokio::fs::try_exists(&src).await? {
+        move_file(&src, &removed_dir.join(clip_path)).await?;
+    }
+
+    // clips live in `<clip id>/video/<file>`, and their burned version in `<clip id>/burned/<file>`
+    let Some(clip_dir) = clip_path.parent().and_then(Path::parent) else {
+        return Ok(());
+    };
+    let Some(file_name) = clip_path.file_name() else {
+        return Ok(());
+    };
+
+    let burned = clip_dir.join("burned").join(file_name);
+    if tokio::fs::try_exists(video_dir.join(&burned)).await? {
+        move_file(&video_dir.join(&burned), &removed_dir.join(&burned)).await?;
+    }
+
+    // take the rest of the clip's folder with it so it doesn't show up as an empty entry when
+    // burning (this also covers folders from before clips were stored by id, which could hold
+    // several clips by the same author)
+    let clip_path_on_disk = video_dir.join(clip_dir);
+    if !dir_has_entries(&clip_path_on_disk.join("video")).await? {
+        for file_name in [USER_INFO_FILE_NAME, CLIP_SETTINGS_FILE_NAME] {
+            let file = clip_dir.join(file_name);
+            if tokio::fs::try_exists(video_dir.join(&file)).await? {
+                move_file(&video_dir.join(&file), &removed_dir.join(&file)).await?;
+            }
+        }
+
+        // the avatar is only a cache, it's downloaded again if the clip ever comes back
+        if let Some(avatar) = download::find_avatar(&clip_path_on_disk) {
+            tokio::fs::remove_file(&avatar)
+                .await
+                .with_context(|| format!("failed to remove {}", avatar.display()))?;
+        }
+
+        for dir in ["video", "burned"] {
+            let _ = tokio::fs::remove_dir(clip_path_on_disk.join(dir)).await;
+        }
+        let _ = tokio::fs::remove_dir(&clip_path_on_disk).await;
+    }
+
+    Ok(())
+}
+
+async fn dir_has_entries(path: &Path) -> Result<bool> {
+    let Ok(mut entries) = tokio::fs::read_dir(path).await else {
+        return Ok(false);
+    };
+
+    Ok(entries.next_entry().await?.is_some())
+}
+
+/// finds every downloaded clip in the video directory that isn't tracked by the manifest
+async fn untracked_clip_files(video_dir: &Path, tracked: &HashSet<&Path>) -> Result<Vec<PathBuf>> {
+    let mut untracked = Vec::new();
+
+    let Ok(mut clip_dirs) = tokio::fs::read_dir(video_dir).await else {
+        return Ok(untracked);
+    };
+
+    while let Some(clip_dir) = clip_dirs.next_entry().await? {
+        let dir_name = clip_dir.file_name();
+        if dir_name == REMOVED_DIR_NAME || !clip_dir.file_type().await?.is_dir() {
+            continue;
+        }
+
+        let Ok(mut videos) = tokio::fs::read_dir(clip_dir.path().join("video")).await else {
+            continue;
+        };
+
+        while let Some(video) = videos.next_entry().await? {
+            let path = video.path();
+            if !video.file_type().await?.is_file() || is_partial_download(&path) {
+                continue;
+            }
+
+            let relative = Path::new(&dir_name).join("video").join(video.file_name());
+            if !tracked.contains(relative.as_path()) {
+                untracked.push(relative);
+            }
+        }
+    }
+
+    Ok(untracked)
+}
+
+fn print_report(added: &[String], removed: &[PathBuf], unchanged: usize, failed: usize) {
+    println!("\n  {}\n", "sync complete".green().bold());
+
+    for title in added {
+        println!("  {} {title}", "+".green().bold());
+    }
+    for path in removed {
+        println!("  {} {}", "-".red().bold(), path.display());
+    }
+
+    if !added.is_empty() || !removed.is_empty() {
+        println!();
+    }
+
+    println!(
+        "  {} added, {} removed, {} unchanged, {} failed\n",
+        added.len(),
+        removed.len(),
+        unchanged,
+        failed
+    );
+
+    if !removed.is_empty() {
+        println!(
+            "  removed clips were moved into `{REMOVED_DIR_NAME}/`, delete it once you're sure\n"
+        );
+    }
+}
@@ -1,14 +1,33 @@
 use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
 use std::{sync::Arc, time::Duration};
-use tempfile::tempdir;
-use tokio::process::Command;
+use tempfile::{TempDir, tempdir};
 use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
-use crate::{api::client::ApiClient, download::download_file_into_temp_dir};
+use crate::{
+    api::{client::ApiClient, clips::Clip},
+    config::Config,
+    download::download_file_into_temp_dir,
+    manifest::Manifest,
+    media_cache::{MediaCache, MediaInfo},
+    probe,
+};
+
+/// where the duration of a clip was read from
+#[derive(Debug)]
+enum DurationSource {
+    /// the clip was already downloaded into `out/`, and got probed in full
+    Local(Box<MediaInfo>),
+    /// ffprobe read it straight from the url
+    Remote,
+    /// probing the url didn't work, so the clip had to be downloaded after all
+    Downloaded,
+}
 
-pub async fn handle(api_client: &ApiClient, video_id: &str) -> Result<Duration> {
+pub async fn handle(config: &Config, api_client: &ApiClient, video_id: &str) -> Result<Duration> {
+    let video_dir = config.fs.out_dir.join(video_id);
     let temp_dir = Arc::new(tempdir()?);
 
     let res = api_client
@@ -21,6 +40,13 @@ pub async fn handle(api_client: &ApiClient, video_id: &str) -> Result<Duration>
         return Ok(Duration::ZERO);
     }
 
+    let mut cache = MediaCache::load(&video_dir)
+        .await
+        .context("failed to load media cache")?;
+    let manifest = Manifest::load(&video_dir)
+        .await
+        .context("failed to load download manifest")?;
+
     let semaphore = Arc::new(Semaphore::new(5));
 
     let pb = ProgressBar::new(total_clips as u64);
@@ -33,11 +59,37 @@ pub async fn handle(api_client: &ApiClient, video_id: &str) -> Result<Duration>
     );
     pb.enable_steady_tick(Duration::from_millis(100));
 
+    let mut total_secs = 0.0;
+    let mut from_cache = 0;
     let mut set = JoinSet::new();
 
     for clip in res.clips {
+        if let Some(duration) = cache.duration_sec(&clip.id, &clip.url) {
+            total_secs += duration;
+            from_cache += 1;
+            pb.inc(1);
+            continue;
+        }
+
+        // a clip we already downloaded is quicker to probe than the url
+        let local_path = manifest
+            .clips
+            .get(&clip.id)
+            .filter(|entry| entry.url == clip.url)
+            .map(|entry| video_dir.join(&entry.path))
+            .filter(|path| path.is_file());
+
+        if let Some(path) = &local_path
+            && let Some(info) = cache.file_info(&clip.id, &video_dir, path).await
+        {
+            total_secs += info.duration_sec;
+            from_cache += 1;
+            pb.inc(1);
+            continue;
+        }
+
         let client = api_client.client.clone();
-        let url = clip.url.clone();
+        let video_dir = video_dir.clone();
         let temp_dir_clone = Arc::clone(&temp_dir);
         let sem = Arc::clone(&semaphore);
         let pb_task = pb.clone();
@@ -47,42 +99,48 @@ pub async fn handle(api_client: &ApiClient, video_id: &str) -> Result<Duration>
         set.spawn(async move {
             let _permit = permit;
 
-            let file_path = download_file_into_temp_dir(&url, &temp_dir_clone, &client).await?;
-
-            let output = Command::new("ffprobe")
-                .args([
-                    "-v",
-                    "error",
-                    "-show_entries",
-                    "format=duration",
-                    "-of",
-                    "default=noprint_wrappers=1:nokey=1",
-                ])
-                .arg(&file_path)
-                .output()
-                .await?;
-
-            if !output.status.success() {
-                anyhow::bail!("ffprobe failed for {}", file_path.display());
-            }
-
-            let duration_str = String::from_utf8_lossy(&output.stdout);
-            let duration: f64 = duration_str.trim().parse()?;
+            let (duration, source) =
+                probe_clip(&clip, &video_dir, local_path, &temp_dir_clone, &client).await?;
 
             pb_task.inc(1);
 
-            Ok::<f64, anyhow::Error>(duration)
+            Ok::<_, anyhow::Error>((clip.id, clip.url, duration, source))
         });
     }
 
-    let mut total_secs = 0.0;
+    let (mut local, mut remote, mut downloaded) = (0, 0, 0);
+
     while let Some(task_result) = set.join_next().await {
-        let clip_duration = task_result.context("task panicked")??;
-        total_secs += clip_duration;
+        let (clip_id, url, duration_sec, source) = task_result.context("task panicked")??;
+        total_secs += duration_sec;
+
+        match &source {
+            DurationSource::Local(_) => local += 1,
+            DurationSource::Remote => remote += 1,
+            DurationSource::Downloaded => downloaded += 1,
+        }
+
+        // local files are cached like `video probe` does, so burning can use them too
+        if let DurationSource::Local(info) = source {
+            cache.set_file_info(clip_id, *info);
+        } else {
+            cache.set_duration_sec(clip_id, url, duration_sec);
+        }
     }
 
     pb.finish_with_message("processing complete");
 
+    if local + remote + downloaded > 0 {
+        cache
+            .save(&video_dir)
+            .await
+            .context("failed to save media cache")?;
+    }
+
+    println!(
+        "probed {remote} clip(s) remotely, {local} locally, {downloaded} by downloading them ({from_cache} cached)"
+    );
+
     let total_duration = Duration::from_secs_f64(total_secs);
     println!(
         "total (approximate) duration (excluding intro, credit text, and stuff like that): {}",
@@ -92,7 +150,31 @@ pub async fn handle(api_client: &ApiClient, video_id: &str) -> Result<Duration>
     Ok(total_duration)
 }
 
-fn format_duration(duration: Duration) -> String {
+async fn probe_clip(
+    clip: &Clip,
+    video_dir: &Path,
+    local_path: Option<PathBuf>,
+    temp_dir: &TempDir,
+    client: &reqwest::Client,
+) -> Result<(f64, DurationSource)> {
+    if let Some(path) = local_path {
+        let info = MediaInfo::probe(video_dir, &path).await?;
+        return Ok((info.duration_sec, DurationSource::Local(Box::new(info))));
+    }
+
+    if let Ok(duration) = probe::duration_sec(clip.url.as_str()).await {
+        return Ok((duration, DurationSource::Remote));
+    }
+
+    // the server (or the local ffprobe build) didn't let us probe over http
+    let file_path = download_file_into_temp_dir(clip, temp_dir, client).await?;
+    let duration = probe::duration_sec(&file_path).await?;
+    let _ = tokio::fs::remove_file(&file_path).await;
+
+    Ok((duration, DurationSource::Downloaded))
+}
+
+pub fn format_duration(duration: Duration) -> String {
     let total_secs = duration.as_secs();
     let hours = total_secs / 3600;
     let minutes = (total_secs % 3600) / 60;
@@ -1,7 +1,8 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 #[derive(Debug, Deserialize)]
@@ -14,12 +15,594 @@ pub struct ApiConfig {
 pub struct FsConfig {
     pub out_dir: std::path::PathBuf,
     pub font_file: std::path::PathBuf,
+    /// template used by `video description` when no `--template` is given
+    pub description_template: Option<std::path::PathBuf>,
+}
+
+/// which corner of the frame the credit text sits in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Anchor {
+    pub const fn is_left(self) -> bool {
+        matches!(self, Self::TopLeft | Self::BottomLeft)
+    }
+
+    pub const fn is_top(self) -> bool {
+        matches!(self, Self::TopLeft | Self::TopRight)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShadowStyle {
+    pub color: String,
+    pub opacity: f64,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Default for ShadowStyle {
+    fn default() -> Self {
+        Self {
+            color: "black".to_string(),
+            opacity: 0.5,
+            x: 2,
+            y: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BorderStyle {
+    pub color: String,
+    pub opacity: f64,
+    pub width: u32,
+}
+
+impl Default for BorderStyle {
+    fn default() -> Self {
+        Self {
+            color: "black".to_string(),
+            opacity: 1.0,
+            width: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BoxStyle {
+    pub color: String,
+    pub opacity: f64,
+    /// space between the text and the edge of the box
+    pub padding: u32,
+}
+
+impl Default for BoxStyle {
+    fn default() -> Self {
+        Self {
+            color: "black".to_string(),
+            opacity: 0.4,
+            padding: 10,
+        }
+    }
+}
+
+/// how the credit text looks, shared by the credit burn and the intro/outro text
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StyleConfig {
+    pub font_size: u32,
+    pub line_spacing: u32,
+    pub color: String,
+    pub opacity: f64,
+    pub anchor: Anchor,
+    /// distance from the left/right edge of the frame
+    pub padding_x: u32,
+    /// distance from the top/bottom edge of the frame
+    pub padding_y: u32,
+    pub shadow: Option<ShadowStyle>,
+    pub border: Option<BorderStyle>,
+    #[serde(rename = "box")]
+    pub text_box: Option<BoxStyle>,
+}
+
+impl Default for StyleConfig {
+    fn default() -> Self {
+        Self {
+            font_size: 34,
+            line_spacing: 6,
+            color: "white".to_string(),
+            opacity: 0.75,
+            anchor: Anchor::default(),
+            padding_x: 50,
+            padding_y: 40,
+            shadow: None,
+            border: None,
+            text_box: None,
+        }
+    }
+}
+
+/// how a line of text moves into (or out of) place
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Effect {
+    #[default]
+    SlideFade,
+    Slide,
+    Fade,
+    /// just appears (or disappears)
+    Cut,
+}
+
+/// which side of the frame text slides in from (and back out to)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    /// the side the text is anchored to
+    #[default]
+    Anchor,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// the curve a slide follows, eased out when entering and eased in when leaving
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    Linear,
+    #[default]
+    Cubic,
+    Quint,
+    /// overshoots a little before settling
+    Back,
+    /// springs back and forth before settling
+    Elastic,
+}
+
+/// seconds a slide takes, unless a preset says otherwise
+const SLIDE_SEC: f64 = 1.0;
+/// seconds a fade takes, unless a preset says otherwise
+const FADE_SEC: f64 = 0.5;
+/// seconds between the lines of a slide, so each line animates slightly after the one above it
+const LINE_STAGGER_SEC: f64 = 0.15;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    pub effect: Effect,
+    pub direction: Direction,
+    pub easing: Easing,
+    /// seconds the slide takes
+    pub duration: f64,
+    /// seconds the fade takes
+    pub fade: f64,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            effect: Effect::default(),
+            direction: Direction::default(),
+            easing: Easing::default(),
+            duration: SLIDE_SEC,
+            fade: FADE_SEC,
+        }
+    }
+}
+
+/// changes to how a preset enters or leaves, anything that isn't set is kept from the preset
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct TransitionOverride {
+    pub effect: Option<Effect>,
+    pub direction: Option<Direction>,
+    pub easing: Option<Easing>,
+    /// seconds the slide takes
+    pub duration: Option<f64>,
+    /// seconds the fade takes
+    pub fade: Option<f64>,
+}
+
+impl TransitionOverride {
+    pub fn apply(self, transition: Transition) -> Transition {
+        Transition {
+            effect: self.effect.unwrap_or(transition.effect),
+            direction: self.direction.unwrap_or(transition.direction),
+            easing: self.easing.unwrap_or(transition.easing),
+            duration: self.duration.unwrap_or(transition.duration),
+            fade: self.fade.unwrap_or(transition.fade),
+        }
+    }
+}
+
+/// ready made animations, `slide` is the original look
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnimationPreset {
+    #[default]
+    Slide,
+    Fade,
+    Pop,
+    Bounce,
+    None,
+}
+
+/// a fully resolved animation, see [`AnimationConfig`]
+#[derive(Debug, Clone, Copy)]
+pub struct Animation {
+    pub enter: Transition,
+    pub exit: Transition,
+    /// seconds between the lines of a slide
+    pub stagger: f64,
+}
+
+impl AnimationPreset {
+    pub fn animation(self) -> Animation {
+        let slide = Transition::default();
+        let stagger = LINE_STAGGER_SEC;
+
+        match self {
+            Self::Slide => Animation {
+                enter: slide,
+                exit: slide,
+                stagger,
+            },
+            Self::Fade => {
+                let fade = Transition {
+                    effect: Effect::Fade,
+                    easing: Easing::Linear,
+                    ..slide
+                };
+                Animation {
+                    enter: fade,
+                    exit: fade,
+                    stagger,
+                }
+            }
+            Self::Pop => Animation {
+                enter: Transition {
+                    direction: Direction::Bottom,
+                    easing: Easing::Back,
+                    duration: 0.6,
+                    fade: 0.3,
+                    ..slide
+                },
+                exit: Transition {
+                    effect: Effect::Fade,
+                    fade: 0.3,
+                    ..slide
+                },
+                stagger: 0.1,
+            },
+            Self::Bounce => Animation {
+                enter: Transition {
+                    effect: Effect::Slide,
+                    easing: Easing::Elastic,
+                    duration: 1.5,
+                    ..slide
+                },
+                exit: slide,
+                stagger,
+            },
+            Self::None => {
+                let cut = Transition {
+                    effect: Effect::Cut,
+                    duration: 0.0,
+                    fade: 0.0,
+                    ..slide
+                };
+                Animation {
+                    enter: cut,
+                    exit: cut,
+                    stagger: 0.0,
+                }
+            }
+        }
+    }
+}
+
+/// how the intro/outro text (and animated credits) move, a preset with optional changes
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AnimationConfig {
+    pub preset: AnimationPreset,
+    /// changes how the preset enters
+    pub enter: TransitionOverride,
+    /// changes how the preset leaves
+    pub exit: TransitionOverride,
+    pub stagger: Option<f64>,
+}
+
+impl AnimationConfig {
+    pub fn animation(&self) -> Animation {
+        let preset = self.preset.animation();
+
+        Animation {
+            enter: self.enter.apply(preset.enter),
+            exit: self.exit.apply(preset.exit),
+            stagger: self.stagger.unwrap_or(preset.stagger),
+        }
+    }
+}
+
+/// how the credit is drawn on regular clips by `burn-credits`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CreditMode {
+    /// on screen for the whole clip
+    #[default]
+    Static,
+    /// slides in and out like the intro/outro text
+    Animated,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CreditsConfig {
+    pub mode: CreditMode,
+    /// seconds into the clip before the credit starts sliding in (`animated` only)
+    pub delay: f64,
+    /// seconds the credit is on screen for, sliding in and out included (`animated` only). it
+    /// stays until the end of the clip if this isn't set
+    pub duration: Option<f64>,
+    /// draw the creator's avatar next to the credit, if they have one (off by default)
+    pub avatar: bool,
+}
+
+impl Default for CreditsConfig {
+    fn default() -> Self {
+        Self {
+            mode: CreditMode::default(),
+            delay: 0.5,
+            duration: None,
+            avatar: false,
+        }
+    }
+}
+
+/// how clips that aren't 16:9 are fitted into the frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FramingMode {
+    /// scale the clip down and fill the rest with `color`
+    #[default]
+    Pad,
+    /// put a blurred, zoomed in copy of the clip behind it
+    BlurFill,
+    /// zoom in until the frame is filled, cutting off the edges
+    CropFill,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FramingConfig {
+    pub mode: FramingMode,
+    /// colour of the bars in `pad` mode, anything ffmpeg understands (`black`, `#1e1e2e`, ...)
+    pub color: String,
+    /// how blurry the background is in `blur-fill` mode
+    pub blur: u32,
+}
+
+impl Default for FramingConfig {
+    fn default() -> Self {
+        Self {
+            mode: FramingMode::default(),
+            color: "black".to_string(),
+            blur: 20,
+        }
+    }
+}
+
+/// EBU R128 loudness normalisation of the clips when burning credits
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LoudnessConfig {
+    pub enabled: bool,
+    /// integrated loudness to aim for, youtube turns anything louder than -14 down
+    pub target_lufs: f64,
+    /// highest true peak allowed, in dBTP
+    pub true_peak: f64,
+    /// loudness range to aim for, in LU
+    pub range: f64,
+}
+
+impl Default for LoudnessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_lufs: -14.0,
+            true_peak: -1.0,
+            range: 11.0,
+        }
+    }
+}
+
+/// profile used when neither `--profile` nor `default_profile` is given
+pub const DEFAULT_PROFILE: &str = "youtube-1080p30";
+
+/// the encoders a profile can use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VideoCodec {
+    #[default]
+    X264,
+    X265,
+    SvtAv1,
+}
+
+impl VideoCodec {
+    /// the name of the ffmpeg encoder
+    pub const fn encoder(self) -> &'static str {
+        match self {
+            Self::X264 => "libx264",
+            Self::X265 => "libx265",
+            Self::SvtAv1 => "libsvtav1",
+        }
+    }
+
+    /// the preset used when a profile doesn't set one, svt-av1 only takes numbers
+    pub const fn default_preset(self) -> &'static str {
+        match self {
+            Self::X264 | Self::X265 => "fast",
+            Self::SvtAv1 => "8",
+        }
+    }
+}
+
+/// resolution, frame rate and encoder settings for everything that gets burned or assembled
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EncodingProfile {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub codec: VideoCodec,
+    /// encoder preset (`fast`, `slow`, ... for x264/x265, `0` to `13` for svt-av1), the codec's
+    /// default if it isn't set
+    pub preset: Option<String>,
+    /// constant quality, the encoder's default is used if neither this nor `bitrate` is set
+    pub crf: Option<u32>,
+    /// target video bitrate (like `8M`), only used when `crf` isn't set
+    pub bitrate: Option<String>,
+    pub pix_fmt: String,
+    pub audio_codec: String,
+    pub audio_bitrate: String,
+}
+
+impl Default for EncodingProfile {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            fps: 30,
+            codec: VideoCodec::X264,
+            preset: None,
+            crf: None,
+            bitrate: None,
+            pix_fmt: "yuv420p".to_string(),
+            audio_codec: "aac".to_string(),
+            audio_bitrate: "192k".to_string(),
+        }
+    }
+}
+
+impl EncodingProfile {
+    pub fn preset(&self) -> &str {
+        self.preset
+            .as_deref()
+            .unwrap_or_else(|| self.codec.default_preset())
+    }
+
+    /// catches presets the encoder won't take, before anything is burned with them
+    fn check_preset(&self) -> Result<()> {
+        let preset = self.preset();
+
+        if self.codec == VideoCodec::SvtAv1 && !preset.parse::<u8>().is_ok_and(|p| p <= 13) {
+            anyhow::bail!(
+                "svt-av1 presets are numbers from 0 (slowest) to 13 (fastest), not `{preset}`"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// the profiles that exist without being configured
+    fn builtin(name: &str) -> Option<Self> {
+        let profile = match name {
+            DEFAULT_PROFILE => Self::default(),
+            "youtube-1440p60" => Self {
+                width: 2560,
+                height: 1440,
+                fps: 60,
+                preset: Some("medium".to_string()),
+                crf: Some(18),
+                ..Self::default()
+            },
+            "archive-hevc" => Self {
+                codec: VideoCodec::X265,
+                preset: Some("slow".to_string()),
+                crf: Some(20),
+                pix_fmt: "yuv420p10le".to_string(),
+                audio_bitrate: "256k".to_string(),
+                ..Self::default()
+            },
+            "preview-480p" => Self {
+                width: 854,
+                height: 480,
+                preset: Some("veryfast".to_string()),
+                crf: Some(30),
+                audio_bitrate: "96k".to_string(),
+                ..Self::default()
+            },
+            _ => return None,
+        };
+
+        Some(profile)
+    }
+}
+
+/// the text shown by `burn-intro-text` before the credit, or by `burn-outro-text` after it
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextSequenceConfig {
+    /// every slide is a list of lines shown together, slides are shown one after the other
+    #[serde(default)]
+    pub slides: Vec<Vec<String>>,
+}
+
+impl TextSequenceConfig {
+    fn default_intro() -> Self {
+        Self {
+            slides: vec![vec![
+                "unless specified".to_string(),
+                "all usernames are for discord".to_string(),
+            ]],
+        }
+    }
+
+    fn default_outro() -> Self {
+        Self {
+            slides: vec![vec![
+                "thank you for watching".to_string(),
+                "please like and subscribe :)".to_string(),
+            ]],
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub api: ApiConfig,
     pub fs: FsConfig,
+    #[serde(default)]
+    pub style: StyleConfig,
+    #[serde(default)]
+    pub animation: AnimationConfig,
+    #[serde(default)]
+    pub credits: CreditsConfig,
+    #[serde(default)]
+    pub framing: FramingConfig,
+    #[serde(default)]
+    pub loudness: LoudnessConfig,
+    /// profile used by every burn when `--profile` isn't given
+    pub default_profile: Option<String>,
+    /// extra encoding profiles (or replacements for the built-in ones), by name
+    #[serde(default)]
+    pub profiles: BTreeMap<String, EncodingProfile>,
+    #[serde(default = "TextSequenceConfig::default_intro")]
+    pub intro: TextSequenceConfig,
+    #[serde(default = "TextSequenceConfig::default_outro")]
+    pub outro: TextSequenceConfig,
 }
 
 impl Config {
@@ -36,4 +619,55 @@ impl Config {
             .try_deserialize()
             .context("failed to deserialize the config")
     }
+
+    /// looks up an encoding profile, falling back to `default_profile` when `name` is `None`
+    pub fn profile(&self, name: Option<&str>) -> Result<EncodingProfile> {
+        let name = name
+            .or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE);
+
+        if let Some(profile) = self.profiles.get(name) {
+            profile
+                .check_preset()
+                .with_context(|| format!("invalid encoding profile `{name}`"))?;
+            return Ok(profile.clone());
+        }
+
+        EncodingProfile::builtin(name).with_context(|| {
+            let configured: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            format!(
+                "unknown encoding profile `{name}` (built in: {DEFAULT_PROFILE}, youtube-1440p60, archive-hevc, preview-480p{}{})",
+                if configured.is_empty() { "" } else { ", configured: " },
+                configured.join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncodingProfile, VideoCodec};
+
+    fn svt_av1(preset: Option<&str>) -> EncodingProfile {
+        EncodingProfile {
+            codec: VideoCodec::SvtAv1,
+            preset: preset.map(str::to_string),
+            ..EncodingProfile::default()
+        }
+    }
+
+    #[test]
+    fn svt_av1_gets_a_numeric_default_preset() {
+        let profile = svt_av1(None);
+
+        assert_eq!(profile.preset(), "8");
+        assert!(profile.check_preset().is_ok());
+    }
+
+    #[test]
+    fn svt_av1_rejects_x264_presets() {
+        assert!(svt_av1(Some("fast")).check_preset().is_err());
+        assert!(svt_av1(Some("14")).check_preset().is_err());
+        assert!(svt_av1(Some("4")).check_preset().is_ok());
+    }
 }
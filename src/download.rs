@@ -1,73 +1,442 @@
 use crate::api::client::ApiClient;
+use crate::api::clips::Clip;
 use crate::config::Config;
+use crate::manifest::{Manifest, ManifestEntry};
 use anyhow::{Context, Result};
-use convert_case::{Case, Casing};
 use futures_util::StreamExt;
 use futures_util::stream;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use reqwest::Url;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_RANGE, RANGE};
+use reqwest::{StatusCode, Url};
 use serde::Deserialize;
 use serde::Serialize;
 use std::borrow::Cow;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use tempfile::TempDir;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// how many times a single download is attempted before giving up on it
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 5;
+/// how long to wait before the first retry, doubled after every failed attempt so a short
+/// network drop doesn't use up all of them at once
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// what happened to a single clip during a download run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadOutcome {
+    Downloaded,
+    /// the clip was already on disk and matched the manifest
+    Skipped,
+}
+
+/// name of the file next to a clip's `video` and `burned` folders holding its [`UserInfo`]
+pub const USER_INFO_FILE_NAME: &str = "user_info.toml";
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct UserInfo<'a> {
     pub user_id: Cow<'a, str>,
     pub display_name: Cow<'a, str>,
     pub username: Cow<'a, str>,
+
+    /// only missing for info files written before clips were stored by id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clip_id: Option<Cow<'a, str>>,
+    /// the title the clip was submitted with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<Cow<'a, str>>,
+    /// where the cached avatar was downloaded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<Cow<'a, str>>,
+}
+
+impl<'a> UserInfo<'a> {
+    /// the credit for a clip, preferring the overridden profile data if there is one
+    pub fn from_clip(clip: &'a Clip) -> Self {
+        let (user_id, display_name, username, avatar_url) =
+            clip.overridden_profile_data.as_ref().map_or_else(
+                || {
+                    (
+                        Cow::Borrowed(clip.creator.id.as_str()),
+                        Cow::Borrowed(clip.creator.name.as_str()),
+                        Cow::Owned(format!("@{}", clip.creator.username)),
+                        clip.creator
+                            .avatar_url
+                            .as_ref()
+                            .map(|url| Cow::Borrowed(url.as_str())),
+                    )
+                },
+                |profile| {
+                    (
+                        Cow::Owned(format!("profile_{}", profile.id)),
+                        Cow::Borrowed(profile.line1.as_str()),
+                        Cow::Borrowed(profile.line2.as_str()),
+                        // overridden profiles don't have a picture, and the creator's would be wrong
+                        None,
+                    )
+                },
+            );
+
+        Self {
+            user_id,
+            display_name,
+            username,
+            clip_id: Some(Cow::Borrowed(clip.id.as_str())),
+            title: Some(Cow::Borrowed(clip.title.as_str())),
+            avatar_url,
+        }
+    }
+
+    pub fn into_owned(self) -> UserInfo<'static> {
+        UserInfo {
+            user_id: Cow::Owned(self.user_id.into_owned()),
+            display_name: Cow::Owned(self.display_name.into_owned()),
+            username: Cow::Owned(self.username.into_owned()),
+            clip_id: self.clip_id.map(|id| Cow::Owned(id.into_owned())),
+            title: self.title.map(|title| Cow::Owned(title.into_owned())),
+            avatar_url: self.avatar_url.map(|url| Cow::Owned(url.into_owned())),
+        }
+    }
+}
+
+/// the extension of the file a url points to, if it has a sensible one
+fn url_extension(url: &Url) -> Option<&str> {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .and_then(|name| Path::new(name).extension())
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// the file name a clip is stored under: its id, plus the extension of the uploaded file
+pub fn clip_file_name(clip: &Clip) -> String {
+    let extension = url_extension(&clip.url).unwrap_or("mp4");
+
+    format!("{}.{extension}", clip.id)
+}
+
+/// writes the [`UserInfo`] for a clip into its folder
+pub async fn write_user_info(clip_dir: &Path, clip: &Clip) -> Result<()> {
+    let file_content = toml::to_string_pretty(&UserInfo::from_clip(clip))
+        .context("failed to serialise user info")?;
+
+    let mut info_file = tokio::fs::File::create(clip_dir.join(USER_INFO_FILE_NAME)).await?;
+    info_file.write_all(file_content.as_bytes()).await?;
+
+    Ok(())
+}
+
+/// name (without the extension) of the creator's avatar, cached next to a clip's [`UserInfo`]
+pub const AVATAR_FILE_STEM: &str = "avatar";
+
+/// the cached avatar in a clip's folder, if there is one
+pub fn find_avatar(clip_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(clip_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| {
+            path.is_file()
+                && !is_partial_download(path)
+                && path
+                    .file_stem()
+                    .is_some_and(|stem| stem == AVATAR_FILE_STEM)
+        })
+}
+
+/// downloads the avatar of the creator of `clip` to `dir`, returning where it ended up
+pub async fn download_avatar(
+    client: &reqwest::Client,
+    clip: &Clip,
+    dir: &Path,
+) -> Result<Option<PathBuf>> {
+    if clip.overridden_profile_data.is_some() {
+        return Ok(None);
+    }
+    let Some(url) = &clip.creator.avatar_url else {
+        return Ok(None);
+    };
+
+    download_avatar_from(client, url, dir).await.map(Some)
+}
+
+async fn download_avatar_from(client: &reqwest::Client, url: &Url, dir: &Path) -> Result<PathBuf> {
+    // ffmpeg looks at what's in the file rather than the extension, so this is only a guess
+    let extension = url_extension(url).unwrap_or("png");
+    let path = dir.join(format!("{AVATAR_FILE_STEM}.{extension}"));
+
+    download_resumable(client, url, &path, None)
+        .await
+        .with_context(|| format!("failed to download avatar from {url}"))?;
+
+    Ok(path)
 }
 
+/// the cached avatar of a clip, downloading it first if it hasn't been yet (for clips downloaded
+/// while avatars were turned off)
+pub async fn ensure_avatar(
+    client: &reqwest::Client,
+    clip_dir: &Path,
+    user_info: &UserInfo<'_>,
+) -> Result<Option<PathBuf>> {
+    if let Some(existing) = find_avatar(clip_dir) {
+        return Ok(Some(existing));
+    }
+    let Some(url) = &user_info.avatar_url else {
+        return Ok(None);
+    };
+
+    let url = Url::parse(url).with_context(|| format!("invalid avatar url `{url}`"))?;
+    download_avatar_from(client, &url, clip_dir).await.map(Some)
+}
+
+/// keeps the cached avatar in a clip's folder in line with its creator's current one, only
+/// downloading it if `fetch` is set (outdated ones are removed either way)
+///
+/// the [`UserInfo`] in the folder says which avatar is cached, so this has to run before it's
+/// replaced by [`write_user_info`]
+async fn cache_avatar(
+    client: &reqwest::Client,
+    clip_dir: &Path,
+    clip: &Clip,
+    fetch: bool,
+) -> Result<()> {
+    let wanted = UserInfo::from_clip(clip).avatar_url;
+    let cached = tokio::fs::read_to_string(clip_dir.join(USER_INFO_FILE_NAME))
+        .await
+        .ok()
+        .and_then(|text| toml::from_str::<UserInfo>(&text).ok())
+        .and_then(|info| info.avatar_url.map(Cow::into_owned));
+    let existing = find_avatar(clip_dir);
+
+    if existing.is_some() && wanted.is_some() && wanted.as_deref() == cached.as_deref() {
+        return Ok(());
+    }
+
+    if let Some(existing) = existing {
+        tokio::fs::remove_file(&existing)
+            .await
+            .with_context(|| format!("failed to remove old avatar {}", existing.display()))?;
+    }
+
+    if fetch {
+        download_avatar(client, clip, clip_dir).await?;
+    }
+
+    Ok(())
+}
+
+/// where a clip is downloaded to, relative to `out/<video>`
+///
+/// everything is keyed by the clip id (`<clip id>/video/<clip id>.mp4`), so two clips can never
+/// end up in the same place no matter what they or their authors are called
+pub fn clip_relative_path(clip: &Clip) -> PathBuf {
+    Path::new(&clip.id).join("video").join(clip_file_name(clip))
+}
+
+/// downloads a clip into `temp_dir`, named after its id
+///
+/// several clips can be downloaded into the same directory at once, so the name has to be
+/// unique, otherwise two of them could end up resuming each other's `.part` file
 pub async fn download_file_into_temp_dir(
-    file_url: &Url,
+    clip: &Clip,
     temp_dir: &TempDir,
     client: &reqwest::Client,
 ) -> Result<PathBuf> {
-    let file_name = file_url
-        .path_segments()
-        .and_then(|mut segments| segments.next_back())
-        .filter(|name| !name.is_empty())
-        .unwrap_or("downloaded_file.tmp");
+    let dest_path = temp_dir.path().join(clip_file_name(clip));
+
+    download_resumable(client, &clip.url, &dest_path, None).await?;
+
+    Ok(dest_path)
+}
+
+/// downloads `url` into `dest`, going through a `.part` file next to it
+///
+/// if a `.part` file is already there (from an earlier, interrupted run) the download continues
+/// from where it stopped using a `Range` request. the file is only renamed to `dest` once the
+/// number of bytes on disk matches what the server said it would send
+pub async fn download_resumable(
+    client: &reqwest::Client,
+    url: &Url,
+    dest: &Path,
+    pb: Option<&ProgressBar>,
+) -> Result<()> {
+    let part_path = part_path(dest);
+    let mut last_error = None;
+    let mut retry_delay = FIRST_RETRY_DELAY;
 
-    let dest_path = temp_dir.path().join(file_name);
+    for attempt in 0..MAX_DOWNLOAD_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(retry_delay).await;
+            retry_delay *= 2;
+        }
 
-    let mut response = client
-        .get(file_url.as_str())
+        match download_attempt(client, url, &part_path, pb).await {
+            Ok(()) => {
+                tokio::fs::rename(&part_path, dest).await.with_context(|| {
+                    format!(
+                        "failed to move {} to {}",
+                        part_path.display(),
+                        dest.display()
+                    )
+                })?;
+
+                return Ok(());
+            }
+            Err(e) => {
+                // no point in retrying if the server told us the file isn't there
+                let is_client_error = e
+                    .downcast_ref::<reqwest::Error>()
+                    .and_then(reqwest::Error::status)
+                    .is_some_and(|status| status.is_client_error());
+
+                if is_client_error {
+                    return Err(e);
+                }
+
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("download was never attempted")))
+        .with_context(|| format!("giving up on {url} after {MAX_DOWNLOAD_ATTEMPTS} attempts"))
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut file_name = dest.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".part");
+
+    dest.with_file_name(file_name)
+}
+
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &Url,
+    part_path: &Path,
+    pb: Option<&ProgressBar>,
+) -> Result<()> {
+    let existing_len = tokio::fs::metadata(part_path)
+        .await
+        .map_or(0, |metadata| metadata.len());
+
+    let mut request = client.get(url.clone());
+    if existing_len > 0 {
+        request = request.header(RANGE, format!("bytes={existing_len}-"));
+    }
+
+    let response = request
         .send()
         .await
-        .context("failed to send HTTP request")?
+        .context("failed to send HTTP request")?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // the server sends the full size as `bytes */<len>`, if that's what we already have
+        // then the previous run just didn't get to rename the file
+        let total_len = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("bytes */"))
+            .and_then(|len| len.parse::<u64>().ok());
+
+        if total_len == Some(existing_len) {
+            return Ok(());
+        }
+
+        tokio::fs::remove_file(part_path)
+            .await
+            .context("failed to remove stale partial download")?;
+        anyhow::bail!("server rejected the resume range, restarting from scratch");
+    }
+
+    let mut response = response
         .error_for_status()
         .context("server returned an error status code")?;
 
-    let mut dest_file = tokio::fs::File::create(&dest_path)
-        .await
-        .with_context(|| format!("failed to create file at {}", dest_path.display()))?;
+    let resuming = existing_len > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    let can_resume = resuming
+        || response
+            .headers()
+            .get(ACCEPT_RANGES)
+            .is_some_and(|value| value.as_bytes() == b"bytes");
+
+    let offset = if resuming { existing_len } else { 0 };
+    let expected_len = response.content_length().map(|len| len + offset);
+
+    let mut dest_file = if resuming {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(part_path)
+            .await
+    } else {
+        tokio::fs::File::create(part_path).await
+    }
+    .with_context(|| format!("failed to open {}", part_path.display()))?;
+
+    if let Some(pb) = pb {
+        if let Some(len) = expected_len {
+            pb.set_length(len);
+        }
+        pb.set_position(offset);
+    }
+
+    let mut written = offset;
+
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                drop(dest_file);
+
+                // the next attempt can't continue from here anyway, so don't leave a
+                // partial file behind that we'd try to resume later
+                if !can_resume {
+                    let _ = tokio::fs::remove_file(part_path).await;
+                }
+
+                return Err(e).context("error reading response chunk");
+            }
+        };
 
-    while let Some(chunk) = response
-        .chunk()
-        .await
-        .context("error reading response chunk")?
-    {
         dest_file
             .write_all(&chunk)
             .await
             .context("failed to write chunk to disk")?;
+
+        written += chunk.len() as u64;
+        if let Some(pb) = pb {
+            pb.inc(chunk.len() as u64);
+        }
     }
 
-    Ok(dest_path)
+    dest_file
+        .flush()
+        .await
+        .context("failed to flush downloaded file")?;
+
+    if let Some(expected_len) = expected_len
+        && written != expected_len
+    {
+        anyhow::bail!("download ended after {written} of {expected_len} bytes");
+    }
+
+    Ok(())
 }
 
 async fn download_clip(
     client: &reqwest::Client,
-    clip: &crate::api::clips::Clip,
+    clip: &Clip,
     video_id: &str,
     base_dir: &Path,
     multi: &MultiProgress,
-) -> anyhow::Result<()> {
+    manifest: &Mutex<Manifest>,
+    fetch_avatar: bool,
+) -> anyhow::Result<DownloadOutcome> {
     let pb = multi.add(ProgressBar::new(0));
     pb.set_style(
         ProgressStyle::with_template(
@@ -77,69 +446,68 @@ async fn download_clip(
     );
     pb.set_message(format!("Downloading {}", clip.title));
 
-    let author_name = clip.overridden_profile_data.as_ref().map_or_else(
-        || clip.creator.username.clone(),
-        |profile| format!("profile__{}", profile.line1),
-    );
-
-    let author_snake = author_name.to_case(Case::Snake);
-    let video_dir = base_dir.join(video_id).join(&author_snake).join("video");
-    let info_path = base_dir
-        .join(video_id)
-        .join(&author_snake)
-        .join("user_info.toml");
-
-    tokio::fs::create_dir_all(&video_dir).await?;
-    let user_info = clip.overridden_profile_data.as_ref().map_or_else(
-        || UserInfo {
-            user_id: Cow::Borrowed(clip.creator.id.as_str()),
-            display_name: Cow::Borrowed(clip.creator.name.as_str()),
-            username: Cow::Owned(format!("@{}", clip.creator.username)),
-        },
-        |profile| UserInfo {
-            user_id: Cow::Owned(format!("profile_{}", profile.id)),
-            display_name: Cow::Borrowed(profile.line1.as_str()),
-            username: Cow::Borrowed(profile.line2.as_str()),
-        },
-    );
+    let video_root = base_dir.join(video_id);
+    let relative_path = clip_relative_path(clip);
+    let path = video_root.join(&relative_path);
+    let clip_dir = video_root.join(&clip.id);
 
-    let file_content =
-        toml::to_string_pretty(&user_info).context("failed to serialise user info")?;
+    tokio::fs::create_dir_all(clip_dir.join("video")).await?;
 
-    let mut info_file = tokio::fs::File::create(&info_path).await?;
-    info_file.write_all(file_content.as_bytes()).await?;
+    // the credit falls back to just the name without an avatar, so this isn't worth failing over
+    if let Err(e) = cache_avatar(client, &clip_dir, clip, fetch_avatar).await {
+        multi.suspend(|| eprintln!("warning: no avatar for {}: {e:#}", clip.title));
+    }
+    write_user_info(&clip_dir, clip).await?;
 
-    let filename = clip
-        .url
-        .path_segments()
-        .and_then(|mut segments| segments.next_back())
-        .filter(|name| !name.is_empty())
-        .unwrap_or("video.mp4");
+    let previous_entry = manifest.lock().await.clips.get(&clip.id).cloned();
 
-    let path = video_dir.join(filename);
+    if let Some(entry) = &previous_entry {
+        if entry.url == clip.url
+            && entry.path == relative_path
+            && entry.matches_local_file(&video_root).await?
+        {
+            pb.finish_with_message(format!("Skipped {}", clip.title));
+            return Ok(DownloadOutcome::Skipped);
+        }
 
-    let response = client
-        .get(clip.url.clone())
-        .send()
-        .await?
-        .error_for_status()?;
+        // the clip changed on the frontend, so anything downloaded or burned from the old
+        // version is outdated
+        let mut outdated = vec![clip_dir.join("burned").join(clip_file_name(clip))];
+        if entry.path != relative_path {
+            outdated.push(video_root.join(&entry.path));
+        }
 
-    if let Some(len) = response.content_length() {
-        pb.set_length(len);
+        for old_path in outdated {
+            if tokio::fs::try_exists(&old_path).await? {
+                tokio::fs::remove_file(&old_path).await.with_context(|| {
+                    format!("failed to remove outdated clip {}", old_path.display())
+                })?;
+            }
+        }
     }
 
-    let mut file = tokio::fs::File::create(&path).await?;
-    let mut stream = response.bytes_stream();
+    download_resumable(client, &clip.url, &path, Some(&pb)).await?;
 
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk?;
-        file.write_all(&chunk).await?;
-        pb.inc(chunk.len() as u64);
-    }
+    pb.set_message(format!("Hashing {}", clip.title));
+    let entry = ManifestEntry::new(clip.url.clone(), &video_root, relative_path).await?;
+
+    // the lock is held while saving so concurrent downloads don't overwrite each other's entries
+    let mut manifest = manifest.lock().await;
+    manifest.clips.insert(clip.id.clone(), entry);
+    manifest.save(&video_root).await?;
+    drop(manifest);
 
     pb.finish_with_message(format!("Saved {}", clip.title));
 
-    Ok(())
+    Ok(DownloadOutcome::Downloaded)
+}
+
+/// how a download run went, clip by clip
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DownloadSummary {
+    pub downloaded: usize,
+    pub skipped: usize,
+    pub failed: usize,
 }
 
 /// downloads selected files from ttcore.gurkz.me
@@ -149,17 +517,29 @@ pub async fn download_selected_files(
     video_id: &str,
     config: &Config,
     api_client: &ApiClient,
-) -> Result<()> {
-    let client = Arc::new(api_client.client.clone());
-    let base_dir = Arc::new(config.fs.out_dir.clone());
-
+) -> Result<DownloadSummary> {
     let selected_clips = api_client
         .list_selected_clips_for_video(video_id, true)
         .await
         .context("failed to fetch clips")?
         .clips;
 
-    let total_files = selected_clips.len() as u64;
+    download_clips(video_id, selected_clips, config, api_client).await
+}
+
+/// downloads the given clips of a video, skipping the ones the manifest says are up to date
+pub async fn download_clips(
+    video_id: &str,
+    clips: Vec<Clip>,
+    config: &Config,
+    api_client: &ApiClient,
+) -> Result<DownloadSummary> {
+    let client = Arc::new(api_client.client.clone());
+    let base_dir = Arc::new(config.fs.out_dir.clone());
+    // avatars are only drawn when they're turned on, `burn-credits` fetches them if that changes
+    let fetch_avatar = config.credits.avatar;
+
+    let total_files = clips.len() as u64;
     let multi = Arc::new(MultiProgress::new());
 
     let overall_pb = multi.add(ProgressBar::new(total_files));
@@ -171,19 +551,51 @@ pub async fn download_selected_files(
     );
 
     let video_id_owned = video_id.to_string();
+    let manifest = Arc::new(Mutex::new(
+        Manifest::load(&base_dir.join(video_id))
+            .await
+            .context("failed to load download manifest")?,
+    ));
+    let downloaded = Arc::new(AtomicUsize::new(0));
+    let skipped = Arc::new(AtomicUsize::new(0));
+    let failed = Arc::new(AtomicUsize::new(0));
 
-    stream::iter(selected_clips)
+    stream::iter(clips)
         .for_each_concurrent(3, |clip| {
             let client = Arc::clone(&client);
             let base_dir = Arc::clone(&base_dir);
             let multi = Arc::clone(&multi);
             let overall_pb = overall_pb.clone();
             let video_id = video_id_owned.clone();
+            let manifest = Arc::clone(&manifest);
+            let downloaded = Arc::clone(&downloaded);
+            let skipped = Arc::clone(&skipped);
+            let failed = Arc::clone(&failed);
 
             async move {
-                match download_clip(&client, &clip, &video_id, &base_dir, &multi).await {
-                    Ok(()) => overall_pb.inc(1),
-                    Err(e) => eprintln!("Failed to download {}: {e:#}", clip.title),
+                match download_clip(
+                    &client,
+                    &clip,
+                    &video_id,
+                    &base_dir,
+                    &multi,
+                    &manifest,
+                    fetch_avatar,
+                )
+                .await
+                {
+                    Ok(outcome) => {
+                        let counter = match outcome {
+                            DownloadOutcome::Downloaded => &downloaded,
+                            DownloadOutcome::Skipped => &skipped,
+                        };
+                        counter.fetch_add(1, Ordering::Relaxed);
+                        overall_pb.inc(1);
+                    }
+                    Err(e) => {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                        eprintln!("Failed to download {}: {e:#}", clip.title);
+                    }
                 }
             }
         })
@@ -191,5 +603,14 @@ pub async fn download_selected_files(
 
     overall_pb.finish_with_message("All downloads complete");
 
-    Ok(())
+    Ok(DownloadSummary {
+        downloaded: downloaded.load(Ordering::Relaxed),
+        skipped: skipped.load(Ordering::Relaxed),
+        failed: failed.load(Ordering::Relaxed),
+    })
+}
+
+/// whether `path` is an unfinished download left behind by [`download_resumable`]
+pub fn is_partial_download(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "part")
 }
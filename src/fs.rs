@@ -68,3 +68,18 @@ pub async fn clean_output_dir(config: &Config) -> Result<()> {
 
     Ok(())
 }
+
+/// moves a file, creating the destination's parent directories if needed
+pub async fn move_file(src: &Path, dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+
+    tokio::fs::rename(src, dest)
+        .await
+        .with_context(|| format!("failed to move {} to {}", src.display(), dest.display()))?;
+
+    Ok(())
+}
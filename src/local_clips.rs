@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::commands::sync::REMOVED_DIR_NAME;
+use crate::download::{USER_INFO_FILE_NAME, UserInfo, is_partial_download};
+use crate::order::ClipOrder;
+
+/// a clip that has been downloaded into `out/<video>/<clip id>/`
+pub struct LocalClip {
+    pub clip_id: String,
+    pub dir: PathBuf,
+    pub user_info: UserInfo<'static>,
+    /// the downloaded file
+    pub video: Option<PathBuf>,
+    /// the output of `burn-credits`, if it has been burned yet
+    pub burned: Option<PathBuf>,
+}
+
+impl LocalClip {
+    /// the burned clip if there is one, otherwise the downloaded one
+    pub fn best_file(&self) -> Option<&Path> {
+        self.burned.as_deref().or(self.video.as_deref())
+    }
+}
+
+/// every downloaded clip of a video, in running order
+pub fn list_local_clips(video_dir: &Path) -> Result<Vec<LocalClip>> {
+    let mut clips = Vec::new();
+
+    let entries = fs::read_dir(video_dir)
+        .with_context(|| format!("failed to read {}", video_dir.display()))?;
+
+    for entry in entries {
+        let dir = entry?.path();
+
+        let Some(dir_name) = dir.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !dir.is_dir() || dir_name == REMOVED_DIR_NAME {
+            continue;
+        }
+
+        let info_path = dir.join(USER_INFO_FILE_NAME);
+        let Ok(text) = fs::read_to_string(&info_path) else {
+            continue;
+        };
+        let Ok(user_info) = toml::from_str::<UserInfo>(&text) else {
+            eprintln!("failed to parse: {}", info_path.display());
+            continue;
+        };
+        let user_info = user_info.into_owned();
+
+        let clip_id = user_info.clip_id.as_deref().unwrap_or(dir_name).to_string();
+
+        clips.push(LocalClip {
+            clip_id,
+            video: first_video_file(&dir.join("video")),
+            burned: first_video_file(&dir.join("burned")),
+            dir,
+            user_info,
+        });
+    }
+
+    clips.sort_by(|a, b| a.dir.cmp(&b.dir));
+
+    if let Some(order) = ClipOrder::load(video_dir)? {
+        order.sort(&mut clips, |clip| Some(clip.clip_id.as_str()));
+    }
+
+    Ok(clips)
+}
+
+/// whether `path` looks like a finished video rather than a leftover from downloading or burning
+pub fn is_video_file(path: &Path) -> bool {
+    let is_error_log = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(".error.log"));
+
+    path.is_file() && !is_error_log && !is_partial_download(path)
+}
+
+/// the first finished video in `dir`, sorted by name
+pub fn first_video_file(dir: &Path) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_video_file(path))
+        .collect();
+
+    files.sort();
+    files.into_iter().next()
+}
@@ -1,10 +1,18 @@
 mod api;
 mod burner;
 mod cli;
+mod clip_settings;
 mod commands;
 mod config;
 mod download;
 mod fs;
+mod local_clips;
+mod manifest;
+mod media_cache;
+mod order;
+mod probe;
+mod template;
+mod timeline;
 
 use clap::Parser;
 
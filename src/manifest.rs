@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.toml";
+
+/// a single downloaded clip, as it was when it finished downloading
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub url: Url,
+    /// path of the downloaded file, relative to the video directory
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    /// unix timestamp (in seconds) of when the download finished
+    pub downloaded_at: u64,
+}
+
+impl ManifestEntry {
+    pub async fn new(url: Url, video_dir: &Path, path: PathBuf) -> Result<Self> {
+        let full_path = video_dir.join(&path);
+        let size = tokio::fs::metadata(&full_path)
+            .await
+            .with_context(|| format!("failed to read metadata of {}", full_path.display()))?
+            .len();
+        let sha256 = sha256_file(&full_path).await?;
+
+        let downloaded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        Ok(Self {
+            url,
+            path,
+            size,
+            sha256,
+            downloaded_at,
+        })
+    }
+
+    /// checks that the file on disk is still the one we downloaded
+    ///
+    /// the size is checked first so we only hash files that have a chance of matching
+    pub async fn matches_local_file(&self, video_dir: &Path) -> Result<bool> {
+        let full_path = video_dir.join(&self.path);
+
+        let Ok(metadata) = tokio::fs::metadata(&full_path).await else {
+            return Ok(false);
+        };
+
+        if !metadata.is_file() || metadata.len() != self.size {
+            return Ok(false);
+        }
+
+        Ok(sha256_file(&full_path).await? == self.sha256)
+    }
+}
+
+/// keeps track of every clip downloaded for a video (`out/<video>/manifest.toml`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub clips: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn path(video_dir: &Path) -> PathBuf {
+        video_dir.join(MANIFEST_FILE_NAME)
+    }
+
+    /// loads the manifest for a video, or an empty one if nothing has been downloaded yet
+    pub async fn load(video_dir: &Path) -> Result<Self> {
+        let path = Self::path(video_dir);
+
+        if !tokio::fs::try_exists(&path).await? {
+            return Ok(Self::default());
+        }
+
+        let text = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub async fn save(&self, video_dir: &Path) -> Result<()> {
+        let path = Self::path(video_dir);
+        let tmp_path = path.with_extension("toml.tmp");
+
+        let text = toml::to_string_pretty(self).context("failed to serialise manifest")?;
+
+        tokio::fs::create_dir_all(video_dir).await?;
+        tokio::fs::write(&tmp_path, text)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("failed to move manifest into {}", path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// hashes a file with sha256, returning the lowercase hex digest
+pub async fn sha256_file(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 1024 * 1024];
+
+        loop {
+            let read = reader
+                .read(&mut buf)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+        }
+
+        let digest = hasher.finalize();
+        let mut hex = String::with_capacity(digest.len() * 2);
+        for byte in digest {
+            let _ = write!(hex, "{byte:02x}");
+        }
+
+        Ok(hex)
+    })
+    .await
+    .context("hashing task panicked")?
+}
@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::clip_settings::Trim;
+use crate::manifest::sha256_file;
+use crate::probe::{self, LoudnessMeasurement};
+
+pub const MEDIA_CACHE_FILE_NAME: &str = "media_cache.toml";
+
+/// bumped whenever [`MediaInfo`] gains a field, so older cache entries get probed again
+const MEDIA_INFO_VERSION: u32 = 2;
+
+/// the duration of a clip, probed straight from its url
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMedia {
+    /// the url of the clip when it was probed, a new url means a new file
+    pub url: Url,
+    pub duration_sec: f64,
+}
+
+/// the size and modification time of a file, used to tell whether a cached probe is stale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    /// unix timestamp (in seconds) of the last modification
+    pub modified: u64,
+}
+
+impl FileStamp {
+    pub async fn read(path: &Path) -> Result<Self> {
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .with_context(|| format!("failed to read metadata of {}", path.display()))?;
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+
+        Ok(Self {
+            size: metadata.len(),
+            modified,
+        })
+    }
+}
+
+/// everything `video probe` found out about a downloaded clip file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaInfo {
+    #[serde(default)]
+    pub version: u32,
+    /// path of the probed file, relative to the video directory
+    pub path: PathBuf,
+    pub stamp: FileStamp,
+    pub sha256: String,
+    pub duration_sec: f64,
+    /// overall bitrate in bits per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bit_rate: Option<u64>,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    #[serde(default)]
+    pub base_fps: f64,
+    pub video_codec: String,
+    /// `None` if the file has no audio stream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_codec: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+    /// only measured when it's needed (`video probe`, or normalising the audio), since it
+    /// decodes the whole file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness: Option<LoudnessMeasurement>,
+    /// the trim the loudness was measured with, the measurement only holds for that part
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness_trim: Option<Trim>,
+}
+
+impl MediaInfo {
+    /// probes a file from scratch (without measuring the loudness)
+    pub async fn probe(video_dir: &Path, path: &Path) -> Result<Self> {
+        let stamp = FileStamp::read(path).await?;
+        let streams = probe::stream_info(path).await?;
+        let sha256 = sha256_file(path).await?;
+
+        Ok(Self {
+            version: MEDIA_INFO_VERSION,
+            path: path.strip_prefix(video_dir).unwrap_or(path).to_path_buf(),
+            stamp,
+            sha256,
+            duration_sec: streams.duration_sec,
+            bit_rate: streams.bit_rate,
+            width: streams.width,
+            height: streams.height,
+            fps: streams.fps,
+            base_fps: streams.base_fps,
+            video_codec: streams.video_codec,
+            audio_codec: streams.audio_codec,
+            sample_rate: streams.sample_rate,
+            loudness: None,
+            loudness_trim: None,
+        })
+    }
+
+    /// the loudness of the part of the file that `trim` keeps, if it has been measured
+    pub fn loudness_for(&self, trim: Option<Trim>) -> Option<&LoudnessMeasurement> {
+        self.loudness
+            .as_ref()
+            .filter(|_| self.loudness_trim == trim)
+    }
+
+    /// measures the loudness of the part of the file that `trim` keeps
+    pub async fn measure_loudness(&mut self, path: &Path, trim: Option<Trim>) -> Result<()> {
+        self.loudness = Some(probe::loudness(path, trim.as_ref()).await?);
+        self.loudness_trim = trim;
+        Ok(())
+    }
+
+    pub const fn has_audio(&self) -> bool {
+        self.audio_codec.is_some()
+    }
+
+    /// whether the frames aren't evenly spaced, which tends to drift out of sync when burned
+    pub fn is_variable_frame_rate(&self) -> bool {
+        self.base_fps > 0.0 && (self.base_fps - self.fps).abs() > 0.1
+    }
+}
+
+/// everything known about a single clip of a video
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CachedClip {
+    /// the duration probed from the api url, for clips that weren't downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<CachedMedia>,
+    /// the full probe of the downloaded file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<MediaInfo>,
+    /// the probe of the output of `burn-credits`, which tells us it's a complete video
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burned: Option<MediaInfo>,
+}
+
+/// probe results for the clips of a video, keyed by clip id (`out/<video>/media_cache.toml`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MediaCache {
+    #[serde(default)]
+    pub clips: BTreeMap<String, CachedClip>,
+}
+
+impl MediaCache {
+    pub fn path(video_dir: &Path) -> PathBuf {
+        video_dir.join(MEDIA_CACHE_FILE_NAME)
+    }
+
+    /// loads the cache for a video, or an empty one if nothing has been probed yet
+    pub async fn load(video_dir: &Path) -> Result<Self> {
+        let path = Self::path(video_dir);
+
+        if !tokio::fs::try_exists(&path).await? {
+            return Ok(Self::default());
+        }
+
+        let text = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub async fn save(&self, video_dir: &Path) -> Result<()> {
+        let path = Self::path(video_dir);
+        let tmp_path = path.with_extension("toml.tmp");
+
+        let text = toml::to_string_pretty(self).context("failed to serialise media cache")?;
+
+        tokio::fs::create_dir_all(video_dir).await?;
+        tokio::fs::write(&tmp_path, text)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("failed to move media cache into {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// the cached duration of a clip, if it was probed with the same url
+    pub fn duration_sec(&self, clip_id: &str, url: &Url) -> Option<f64> {
+        self.clips
+            .get(clip_id)?
+            .remote
+            .as_ref()
+            .filter(|media| &media.url == url)
+            .map(|media| media.duration_sec)
+    }
+
+    pub fn set_duration_sec(&mut self, clip_id: String, url: Url, duration_sec: f64) {
+        self.clips.entry(clip_id).or_default().remote = Some(CachedMedia { url, duration_sec });
+    }
+
+    /// the last probe of a clip's downloaded file, without checking whether it's still up to date
+    pub fn last_file_info(&self, clip_id: &str) -> Option<&MediaInfo> {
+        self.clips.get(clip_id)?.file.as_ref()
+    }
+
+    /// the cached probe of a clip's downloaded file, if the file hasn't changed since
+    pub async fn file_info(
+        &self,
+        clip_id: &str,
+        video_dir: &Path,
+        path: &Path,
+    ) -> Option<&MediaInfo> {
+        fresh(self.last_file_info(clip_id)?, video_dir, path).await
+    }
+
+    pub fn set_file_info(&mut self, clip_id: String, info: MediaInfo) {
+        self.clips.entry(clip_id).or_default().file = Some(info);
+    }
+
+    /// the cached probe of a clip's burned file, if the file hasn't changed since
+    pub async fn burned_info(
+        &self,
+        clip_id: &str,
+        video_dir: &Path,
+        path: &Path,
+    ) -> Option<&MediaInfo> {
+        fresh(self.clips.get(clip_id)?.burned.as_ref()?, video_dir, path).await
+    }
+
+    pub fn set_burned_info(&mut self, clip_id: String, info: MediaInfo) {
+        self.clips.entry(clip_id).or_default().burned = Some(info);
+    }
+}
+
+/// `info`, if it's a probe of `path` as it is on disk right now
+async fn fresh<'a>(info: &'a MediaInfo, video_dir: &Path, path: &Path) -> Option<&'a MediaInfo> {
+    if info.version != MEDIA_INFO_VERSION || video_dir.join(&info.path) != path {
+        return None;
+    }
+
+    let stamp = FileStamp::read(path).await.ok()?;
+    (info.stamp == stamp).then_some(info)
+}
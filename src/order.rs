@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::api::clips::Clip;
+use crate::download::UserInfo;
+
+pub const ORDER_FILE_NAME: &str = "order.toml";
+
+const ORDER_FILE_HEADER: &str = "\
+# the running order of the clips in this video, top to bottom
+# move the [[clips]] entries around to change it (only clip_id matters, the rest is for you)
+
+";
+
+/// what a generated running order is sorted by
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum OrderBy {
+    /// when the clip was submitted
+    #[default]
+    CreatedAt,
+    /// the title the clip was submitted with
+    Title,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderEntry {
+    pub clip_id: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub credit: String,
+}
+
+impl OrderEntry {
+    fn from_clip(clip: &Clip) -> Self {
+        let user_info = UserInfo::from_clip(clip);
+
+        Self {
+            clip_id: clip.id.clone(),
+            title: clip.title.clone(),
+            credit: format!("{} ({})", user_info.display_name, user_info.username),
+        }
+    }
+}
+
+/// the intended running order of the clips in a video (`out/<video>/order.toml`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClipOrder {
+    #[serde(default)]
+    pub clips: Vec<OrderEntry>,
+}
+
+impl ClipOrder {
+    pub fn path(video_dir: &Path) -> std::path::PathBuf {
+        video_dir.join(ORDER_FILE_NAME)
+    }
+
+    /// loads the running order of a video, if one has been created
+    pub fn load(video_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(video_dir);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        toml::from_str(&text)
+            .map(Some)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, video_dir: &Path) -> Result<()> {
+        let path = Self::path(video_dir);
+        let text = toml::to_string_pretty(self).context("failed to serialise clip order")?;
+
+        fs::create_dir_all(video_dir)?;
+        fs::write(&path, format!("{ORDER_FILE_HEADER}{text}"))
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// a running order for `clips`, sorted by `by`
+    pub fn generate(clips: &[Clip], by: OrderBy) -> Self {
+        let mut sorted: Vec<&Clip> = clips.iter().collect();
+        sort_clips(&mut sorted, by);
+
+        Self {
+            clips: sorted.into_iter().map(OrderEntry::from_clip).collect(),
+        }
+    }
+
+    /// brings the order up to date with `clips`, keeping the order of the clips already in it
+    ///
+    /// clips that are gone are dropped, and new ones are added at the end (sorted by `by`)
+    pub fn update(&mut self, clips: &[Clip], by: OrderBy) {
+        let current: HashSet<&str> = clips.iter().map(|c| c.id.as_str()).collect();
+        self.clips
+            .retain(|entry| current.contains(entry.clip_id.as_str()));
+
+        let known: HashSet<String> = self.clips.iter().map(|e| e.clip_id.clone()).collect();
+        let mut new_clips: Vec<&Clip> = clips.iter().filter(|c| !known.contains(&c.id)).collect();
+        sort_clips(&mut new_clips, by);
+
+        self.clips
+            .extend(new_clips.into_iter().map(OrderEntry::from_clip));
+    }
+
+    pub fn position(&self, clip_id: &str) -> Option<usize> {
+        self.clips.iter().position(|entry| entry.clip_id == clip_id)
+    }
+
+    /// sorts `items` into running order, anything not in the order goes last (in the order it
+    /// was already in)
+    pub fn sort<T>(&self, items: &mut [T], clip_id: impl Fn(&T) -> Option<&str>) {
+        items.sort_by_key(|item| {
+            clip_id(item)
+                .and_then(|id| self.position(id))
+                .unwrap_or(usize::MAX)
+        });
+    }
+}
+
+fn sort_clips(clips: &mut [&Clip], by: OrderBy) {
+    match by {
+        OrderBy::CreatedAt => clips.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+        OrderBy::Title => clips.sort_by_key(|c| c.title.to_lowercase()),
+    }
+}
+
+/// the id of the clip a file in `out/<video>/<clip id>/<video|burned>/` belongs to
+pub fn clip_id_from_path(path: &Path) -> Option<&str> {
+    path.parent()?.parent()?.file_name()?.to_str()
+}
+
+/// sorts files belonging to clips of a video into running order, falling back to sorting them by
+/// path if the video has no order yet
+pub fn sort_clip_files<T>(
+    video_dir: &Path,
+    items: &mut [T],
+    path: impl Fn(&T) -> &Path,
+) -> Result<()> {
+    items.sort_by(|a, b| path(a).cmp(path(b)));
+
+    if let Some(order) = ClipOrder::load(video_dir)? {
+        order.sort(items, |item| clip_id_from_path(path(item)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,411 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::clip_settings::Trim;
+
+/// gets the duration of a local file or a url with ffprobe
+///
+/// for urls ffprobe only fetches the parts of the file it needs (the mp4 `moov` box), so this
+/// doesn't download the whole clip
+pub async fn duration_sec(input: impl AsRef<OsStr>) -> Result<f64> {
+    let input = input.as_ref();
+
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+        ])
+        .arg(input)
+        .output()
+        .await
+        .context("failed to run ffprobe")?;
+
+    if !output.status.success() {
+        anyhow::bail!("ffprobe failed for {}", input.to_string_lossy());
+    }
+
+    let duration_str = String::from_utf8_lossy(&output.stdout);
+    duration_str
+        .trim()
+        .parse()
+        .context("failed to parse video duration")
+}
+
+/// the stream properties of a local file that matter for encoding
+#[derive(Debug, Clone)]
+pub struct StreamInfo {
+    pub duration_sec: f64,
+    /// overall bitrate in bits per second, if the container reports one
+    pub bit_rate: Option<u64>,
+    pub width: u32,
+    pub height: u32,
+    /// average frame rate
+    pub fps: f64,
+    /// the lowest frame rate all timestamps fit into, differs from `fps` for variable frame rate
+    pub base_fps: f64,
+    pub video_codec: String,
+    /// `None` if the file has no audio stream
+    pub audio_codec: Option<String>,
+    pub sample_rate: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+    format: FfprobeFormat,
+}
+
+#[derive(Deserialize)]
+struct FfprobeStream {
+    codec_type: String,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    sample_rate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeFormat {
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+/// reads the duration, resolution, frame rate and codecs of a file in one ffprobe call
+pub async fn stream_info(path: &Path) -> Result<StreamInfo> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration,bit_rate:stream=codec_type,codec_name,width,height,avg_frame_rate,r_frame_rate,sample_rate",
+            "-of",
+            "json",
+        ])
+        .arg(path)
+        .output()
+        .await
+        .context("failed to run ffprobe")?;
+
+    if !output.status.success() {
+        anyhow::bail!("ffprobe failed for {}", path.display());
+    }
+
+    let probed: FfprobeOutput = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("failed to parse ffprobe output for {}", path.display()))?;
+
+    let video = probed
+        .streams
+        .iter()
+        .find(|s| s.codec_type == "video")
+        .with_context(|| format!("{} has no video stream", path.display()))?;
+    let audio = probed.streams.iter().find(|s| s.codec_type == "audio");
+
+    let duration_sec = probed
+        .format
+        .duration
+        .as_deref()
+        .context("ffprobe didn't report a duration")?
+        .trim()
+        .parse()
+        .context("failed to parse video duration")?;
+
+    let base_fps = video
+        .r_frame_rate
+        .as_deref()
+        .and_then(parse_frame_rate)
+        .unwrap_or_default();
+    // some containers report 0/0 as the average, the base rate is the next best thing
+    let fps = video
+        .avg_frame_rate
+        .as_deref()
+        .and_then(parse_frame_rate)
+        .unwrap_or(base_fps);
+
+    Ok(StreamInfo {
+        duration_sec,
+        bit_rate: probed.format.bit_rate.and_then(|rate| rate.parse().ok()),
+        width: video.width.unwrap_or_default(),
+        height: video.height.unwrap_or_default(),
+        fps,
+        base_fps,
+        video_codec: video.codec_name.clone().unwrap_or_default(),
+        audio_codec: audio.map(|s| s.codec_name.clone().unwrap_or_default()),
+        sample_rate: audio
+            .and_then(|s| s.sample_rate.as_deref())
+            .and_then(|rate| rate.parse().ok()),
+    })
+}
+
+/// what the analysis pass of ffmpeg's `loudnorm` filter measured, used to normalise the clip
+/// accurately in a second pass
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    /// integrated loudness in LUFS
+    pub integrated: f64,
+    /// true peak in dBTP
+    pub true_peak: f64,
+    /// loudness range in LU
+    pub range: f64,
+    pub threshold: f64,
+}
+
+/// the fields of the json summary printed by `loudnorm=print_format=json`
+#[derive(Deserialize)]
+struct LoudnormOutput {
+    #[serde(rename = "input_i")]
+    integrated: String,
+    #[serde(rename = "input_tp")]
+    true_peak: String,
+    #[serde(rename = "input_lra")]
+    range: String,
+    #[serde(rename = "input_thresh")]
+    threshold: String,
+}
+
+/// measures the loudness of a file's audio (EBU R128) with the analysis pass of `loudnorm`,
+/// limited to `trim` if there is one
+///
+/// this decodes the whole file, so it's a lot slower than the other probes
+pub async fn loudness(path: &Path, trim: Option<&Trim>) -> Result<LoudnessMeasurement> {
+    // only the part that ends up in the video counts, the trimmed off bits are often silent
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats"])
+        .args(trim.map(Trim::input_args).unwrap_or_default())
+        .arg("-i")
+        .arg(path)
+        .args([
+            "-vn",
+            "-af",
+            "loudnorm=print_format=json",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .await
+        .context("failed to run ffmpeg")?;
+
+    if !output.status.success() {
+        anyhow::bail!("ffmpeg failed to measure loudness of {}", path.display());
+    }
+
+    parse_loudnorm_output(&String::from_utf8_lossy(&output.stderr))
+        .with_context(|| format!("failed to read the loudness of {}", path.display()))
+}
+
+/// reads the json summary `loudnorm=print_format=json` prints at the end of ffmpeg's output
+///
+/// silent audio is reported as `-inf`, which is kept as is
+fn parse_loudnorm_output(stderr: &str) -> Result<LoudnessMeasurement> {
+    let json = stderr
+        .rfind('{')
+        .zip(stderr.rfind('}'))
+        .and_then(|(start, end)| stderr.get(start..=end))
+        .context("ffmpeg didn't report the loudness")?;
+
+    let measured: LoudnormOutput =
+        serde_json::from_str(json).context("failed to parse the loudnorm summary")?;
+
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<f64>()
+            .with_context(|| format!("invalid loudness value `{value}`"))
+    };
+
+    Ok(LoudnessMeasurement {
+        integrated: parse(&measured.integrated)?,
+        true_peak: parse(&measured.true_peak)?,
+        range: parse(&measured.range)?,
+        threshold: parse(&measured.threshold)?,
+    })
+}
+
+/// a stretch of a file, in seconds
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// the black frames and silence found in a file
+#[derive(Debug, Default)]
+pub struct Gaps {
+    pub black: Vec<Span>,
+    pub silence: Vec<Span>,
+}
+
+/// finds black frames (`blackdetect`) and silence (`silencedetect`) in a file
+///
+/// like the loudness, this decodes the whole file
+pub async fn detect_gaps(path: &Path, duration_sec: f64) -> Result<Gaps> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(path)
+        .args([
+            "-vf",
+            "blackdetect=d=0.1:pix_th=0.10",
+            "-af",
+            "silencedetect=noise=-50dB:d=0.5",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .await
+        .context("failed to run ffmpeg")?;
+
+    if !output.status.success() {
+        anyhow::bail!("ffmpeg failed to scan {}", path.display());
+    }
+
+    Ok(parse_gaps(
+        &String::from_utf8_lossy(&output.stderr),
+        duration_sec,
+    ))
+}
+
+/// the gaps in the log of a `blackdetect`/`silencedetect` run
+fn parse_gaps(stderr: &str, duration_sec: f64) -> Gaps {
+    let mut gaps = Gaps::default();
+    let mut silence_start = None;
+
+    for line in stderr.lines() {
+        if let (Some(start), Some(end)) = (
+            value_after(line, "black_start:"),
+            value_after(line, "black_end:"),
+        ) {
+            gaps.black.push(Span { start, end });
+        } else if let Some(start) = value_after(line, "silence_start:") {
+            silence_start = Some(start);
+        } else if let Some(end) = value_after(line, "silence_end:")
+            && let Some(start) = silence_start.take()
+        {
+            gaps.silence.push(Span { start, end });
+        }
+    }
+
+    // silence that lasts until the end of the file doesn't always get an end
+    if let Some(start) = silence_start {
+        gaps.silence.push(Span {
+            start,
+            end: duration_sec,
+        });
+    }
+
+    gaps
+}
+
+/// the number right after `key` in a line of ffmpeg's log (`black_start:1.5`, `silence_end: 3`)
+fn value_after(line: &str, key: &str) -> Option<f64> {
+    let (_, rest) = line.split_once(key)?;
+    rest.trim_start()
+        .split(|c: char| c.is_whitespace() || c == '|')
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.split_once('/')?;
+    let num: f64 = num.parse().ok()?;
+    let den: f64 = den.parse().ok()?;
+
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::{parse_gaps, parse_loudnorm_output, value_after};
+
+    const SUMMARY: &str = r#"[Parsed_loudnorm_0 @ 0x5581c0c2a7c0]
+{
+	"input_i" : "-23.51",
+	"input_tp" : "-4.20",
+	"input_lra" : "6.70",
+	"input_thresh" : "-33.83",
+	"output_i" : "-14.02",
+	"output_tp" : "-1.00",
+	"output_lra" : "5.20",
+	"output_thresh" : "-24.30",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.02"
+}
+"#;
+
+    #[test]
+    fn parses_the_loudnorm_summary() -> Result<()> {
+        let stderr = format!("Input #0, mov,mp4 {{ some noise }}\n{SUMMARY}");
+        let measured = parse_loudnorm_output(&stderr)?;
+
+        assert!((measured.integrated - -23.51).abs() < f64::EPSILON);
+        assert!((measured.true_peak - -4.2).abs() < f64::EPSILON);
+        assert!((measured.range - 6.7).abs() < f64::EPSILON);
+        assert!((measured.threshold - -33.83).abs() < f64::EPSILON);
+
+        Ok(())
+    }
+
+    #[test]
+    fn silence_is_negative_infinity() -> Result<()> {
+        let stderr = SUMMARY
+            .replace("\"-23.51\"", "\"-inf\"")
+            .replace("\"-33.83\"", "\"-inf\"")
+            .replace("\"-4.20\"", "\"-inf\"");
+        let measured = parse_loudnorm_output(&stderr)?;
+
+        assert!(measured.integrated.is_infinite() && measured.integrated.is_sign_negative());
+        assert!(measured.threshold.is_infinite());
+        assert!(measured.true_peak.is_infinite());
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_summary_is_an_error() {
+        assert!(parse_loudnorm_output("Output #0, null, to 'pipe:'").is_err());
+        assert!(parse_loudnorm_output("{ \"input_i\" : \"-20.0\" }").is_err());
+    }
+
+    #[test]
+    fn reads_values_after_keys() {
+        let line = "[blackdetect @ 0x1] black_start:0 black_end:1.52 black_duration:1.52";
+        assert_eq!(value_after(line, "black_end:"), Some(1.52));
+
+        let line = "[silencedetect @ 0x2] silence_end: 3.25 | silence_duration: 2";
+        assert_eq!(value_after(line, "silence_end:"), Some(3.25));
+
+        assert_eq!(value_after(line, "black_end:"), None);
+        assert_eq!(value_after("silence_start: nope", "silence_start:"), None);
+    }
+
+    #[test]
+    fn silence_without_an_end_lasts_until_the_end_of_the_file() {
+        let log = "\
+[blackdetect @ 0x1] black_start:0 black_end:0.8 black_duration:0.8
+[silencedetect @ 0x2] silence_start: 0
+[silencedetect @ 0x2] silence_end: 1.5 | silence_duration: 1.5
+[silencedetect @ 0x2] silence_start: 12.5
+";
+        let gaps = parse_gaps(log, 14.0);
+
+        assert_eq!(gaps.black.len(), 1);
+        assert_eq!((gaps.black[0].start, gaps.black[0].end), (0.0, 0.8));
+        assert_eq!(gaps.silence.len(), 2);
+        assert_eq!((gaps.silence[0].start, gaps.silence[0].end), (0.0, 1.5));
+        assert_eq!((gaps.silence[1].start, gaps.silence[1].end), (12.5, 14.0));
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+/// fills in every `{{ name }}` placeholder in `template` with its value
+///
+/// placeholders without a value are an error rather than being left in, so a typo doesn't end
+/// up in a published description
+pub fn render(template: &str, values: &HashMap<&str, String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            anyhow::bail!("unclosed `{{{{` in template");
+        };
+
+        let name = after_open[..end].trim();
+        let Some(value) = values.get(name) else {
+            let mut known: Vec<&str> = values.keys().copied().collect();
+            known.sort_unstable();
+            anyhow::bail!(
+                "unknown placeholder `{{{{{name}}}}}` in template (available: {})",
+                known.join(", ")
+            );
+        };
+
+        out.push_str(value);
+        rest = &after_open[end + 2..];
+    }
+
+    out.push_str(rest);
+
+    Ok(out)
+}
@@ -0,0 +1,57 @@
+use std::fmt::Write as _;
+
+use super::Timeline;
+
+/// where the record side of the timeline starts, the usual default in editors
+const RECORD_START_HOURS: u64 = 1;
+
+/// renders the timeline as a CMX 3600 edit decision list
+///
+/// the credits become `* LOC:` markers, which resolve and premiere both import
+pub fn render(timeline: &Timeline) -> String {
+    let fps = u64::from(timeline.fps);
+    let mut out = String::new();
+
+    let _ = writeln!(out, "TITLE: {}", timeline.name);
+    let _ = writeln!(out, "FCM: NON-DROP FRAME\n");
+
+    let mut record_in = RECORD_START_HOURS * 3600 * fps;
+
+    for (index, clip) in timeline.clips.iter().enumerate() {
+        let record_out = record_in + clip.frames;
+        let channels = if clip.has_audio { "AA/V" } else { "V" };
+
+        let _ = writeln!(
+            out,
+            "{:03}  AX       {channels:<5} C        {} {} {} {}",
+            index + 1,
+            timecode(0, fps),
+            timecode(clip.frames, fps),
+            timecode(record_in, fps),
+            timecode(record_out, fps),
+        );
+        let _ = writeln!(out, "* FROM CLIP NAME: {}", clip.file_name());
+
+        if let Some(marker) = &clip.marker {
+            let _ = writeln!(out, "* LOC: {} GREEN {marker}", timecode(record_in, fps));
+        }
+
+        out.push('\n');
+        record_in = record_out;
+    }
+
+    out
+}
+
+fn timecode(frames: u64, fps: u64) -> String {
+    let fps = fps.max(1);
+    let total_secs = frames / fps;
+
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        total_secs / 3600,
+        (total_secs % 3600) / 60,
+        total_secs % 60,
+        frames % fps
+    )
+}
@@ -0,0 +1,94 @@
+use std::fmt::Write as _;
+
+use anyhow::Result;
+
+use super::Timeline;
+
+/// renders the timeline as final cut pro xml (version 1.9, which resolve imports too)
+///
+/// the credits are added as markers at the start of every clip
+pub fn render(timeline: &Timeline) -> Result<String> {
+    let fps = timeline.fps;
+    let time = |frames: u64| format!("{frames}/{fps}s");
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(out, "<!DOCTYPE fcpxml>");
+    let _ = writeln!(out, r#"<fcpxml version="1.9">"#);
+    let _ = writeln!(out, "  <resources>");
+    let _ = writeln!(
+        out,
+        r#"    <format id="r0" frameDuration="1/{fps}s" width="{}" height="{}"/>"#,
+        timeline.width, timeline.height
+    );
+
+    for (index, clip) in timeline.clips.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            r#"    <asset id="r{}" name="{}" start="0s" duration="{}" hasVideo="1" hasAudio="{}" format="r0">"#,
+            index + 1,
+            escape(&clip.name),
+            time(clip.frames),
+            u8::from(clip.has_audio)
+        );
+        let _ = writeln!(
+            out,
+            r#"      <media-rep kind="original-media" src="{}"/>"#,
+            escape(clip.file_url()?.as_str())
+        );
+        let _ = writeln!(out, "    </asset>");
+    }
+
+    let name = escape(&timeline.name);
+    let _ = writeln!(out, "  </resources>");
+    let _ = writeln!(out, "  <library>");
+    let _ = writeln!(out, r#"    <event name="{name}">"#);
+    let _ = writeln!(out, r#"      <project name="{name}">"#);
+    let _ = writeln!(
+        out,
+        r#"        <sequence format="r0" duration="{}" tcStart="0s" tcFormat="NDF" audioLayout="stereo" audioRate="48k">"#,
+        time(timeline.total_frames())
+    );
+    let _ = writeln!(out, "          <spine>");
+
+    let mut offset = 0;
+    for (index, clip) in timeline.clips.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            r#"            <asset-clip ref="r{}" name="{}" offset="{}" start="0s" duration="{}" format="r0" tcFormat="NDF">"#,
+            index + 1,
+            escape(&clip.name),
+            time(offset),
+            time(clip.frames)
+        );
+
+        if let Some(marker) = &clip.marker {
+            let _ = writeln!(
+                out,
+                r#"              <marker start="0s" duration="{}" value="{}"/>"#,
+                time(1),
+                escape(marker)
+            );
+        }
+
+        let _ = writeln!(out, "            </asset-clip>");
+        offset += clip.frames;
+    }
+
+    let _ = writeln!(out, "          </spine>");
+    let _ = writeln!(out, "        </sequence>");
+    let _ = writeln!(out, "      </project>");
+    let _ = writeln!(out, "    </event>");
+    let _ = writeln!(out, "  </library>");
+    let _ = writeln!(out, "</fcpxml>");
+
+    Ok(out)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
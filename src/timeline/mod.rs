@@ -0,0 +1,71 @@
+pub mod edl;
+pub mod fcpxml;
+pub mod otio;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use url::Url;
+
+use crate::burner::utils::{get_video_duration_sec, has_audio_stream};
+
+/// a clip placed on the timeline, right after the one before it
+pub struct TimelineClip {
+    pub name: String,
+    /// absolute path of the media file
+    pub path: PathBuf,
+    pub frames: u64,
+    pub has_audio: bool,
+    /// text for a marker at the start of the clip (the credit)
+    pub marker: Option<String>,
+}
+
+impl TimelineClip {
+    pub fn probe(name: String, path: &Path, fps: u32, marker: Option<String>) -> Result<Self> {
+        let path = std::path::absolute(path)
+            .with_context(|| format!("failed to resolve {}", path.display()))?;
+
+        let duration_sec = get_video_duration_sec(&path)
+            .with_context(|| format!("failed to get the duration of {}", path.display()))?;
+
+        Ok(Self {
+            name,
+            frames: seconds_to_frames(duration_sec, fps),
+            has_audio: has_audio_stream(&path)?,
+            path,
+            marker,
+        })
+    }
+
+    pub fn file_url(&self) -> Result<Url> {
+        Url::from_file_path(&self.path)
+            .map_err(|()| anyhow::anyhow!("{} is not an absolute path", self.path.display()))
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(|| self.name.clone(), |n| n.to_string_lossy().into_owned())
+    }
+}
+
+/// a single-track sequence of clips, written out by one of the exporters
+pub struct Timeline {
+    pub name: String,
+    pub fps: u32,
+    pub width: u32,
+    pub height: u32,
+    pub clips: Vec<TimelineClip>,
+}
+
+impl Timeline {
+    pub fn total_frames(&self) -> u64 {
+        self.clips.iter().map(|clip| clip.frames).sum()
+    }
+}
+
+fn seconds_to_frames(seconds: f64, fps: u32) -> u64 {
+    let millis = std::time::Duration::from_secs_f64(seconds.max(0.0)).as_millis();
+
+    u64::try_from((millis * u128::from(fps) + 500) / 1000).unwrap_or(u64::MAX)
+}
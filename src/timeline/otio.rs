@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+
+use super::{Timeline, TimelineClip};
+
+/// renders the timeline as an opentimelineio (`.otio`) file
+///
+/// the credits are added as markers at the start of every clip
+pub fn render(timeline: &Timeline) -> Result<String> {
+    let fps = timeline.fps;
+
+    let clips = timeline
+        .clips
+        .iter()
+        .map(|clip| clip_json(clip, fps))
+        .collect::<Result<Vec<_>>>()?;
+
+    let document = json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": timeline.name,
+        "global_start_time": null,
+        "metadata": {},
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "source_range": null,
+            "effects": [],
+            "markers": [],
+            "metadata": {},
+            "children": [{
+                "OTIO_SCHEMA": "Track.1",
+                "name": "Video 1",
+                "kind": "Video",
+                "source_range": null,
+                "effects": [],
+                "markers": [],
+                "metadata": {},
+                "children": clips,
+            }],
+        },
+    });
+
+    serde_json::to_string_pretty(&document).context("failed to serialise otio timeline")
+}
+
+fn clip_json(clip: &TimelineClip, fps: u32) -> Result<Value> {
+    let markers: Vec<Value> = clip
+        .marker
+        .iter()
+        .map(|marker| {
+            json!({
+                "OTIO_SCHEMA": "Marker.2",
+                "name": marker,
+                "color": "GREEN",
+                "comment": "",
+                "metadata": {},
+                "marked_range": time_range(0, 0, fps),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "OTIO_SCHEMA": "Clip.2",
+        "name": clip.name,
+        "source_range": time_range(0, clip.frames, fps),
+        "effects": [],
+        "markers": markers,
+        "metadata": {},
+        "active_media_reference_key": "DEFAULT_MEDIA",
+        "media_references": {
+            "DEFAULT_MEDIA": {
+                "OTIO_SCHEMA": "ExternalReference.1",
+                "name": clip.file_name(),
+                "target_url": clip.file_url()?.as_str(),
+                "available_range": null,
+                "metadata": {},
+            },
+        },
+    }))
+}
+
+fn time_range(start: u64, duration: u64, fps: u32) -> Value {
+    json!({
+        "OTIO_SCHEMA": "TimeRange.1",
+        "start_time": rational_time(start, fps),
+        "duration": rational_time(duration, fps),
+    })
+}
+
+fn rational_time(frames: u64, fps: u32) -> Value {
+    json!({
+        "OTIO_SCHEMA": "RationalTime.1",
+        "rate": f64::from(fps),
+        "value": frames,
+    })
+}